@@ -0,0 +1,92 @@
+//! Compares the flush policies when printing styled text cell by cell, along
+//! with a copy of how printing worked before the policies were added.
+//!
+//! The cells go to stdout and the timings to stderr. Run with stdout on a
+//! terminal, as in `cargo bench --bench print 2> timings.txt`, to measure the
+//! cost of writing to it, or redirect stdout to `/dev/null` to measure
+//! rendering alone.
+
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use term_mods::{ColorPolicy, FlushPolicy, Style, Term};
+
+const ROWS: u32 = 200;
+const COLS: u32 = 100;
+
+// How each cell is printed.
+#[derive(Clone, Copy)]
+enum Printer {
+    // The printing code from before flush policies, copied below.
+    Baseline,
+    Policy(FlushPolicy),
+}
+
+// Prints a styled string the way `Style::print` and `Style::println` did
+// before flush policies: the text is formatted into a new `String`, then
+// written and flushed through a fresh stdout handle each time.
+fn print_baseline(style: &mut Style<'_>, newline: bool) -> io::Result<()> {
+    let mut ansi_string = match style.text.len() {
+        0 => return Ok(()),
+        _ => style.get_ansi(),
+    };
+
+    if newline {
+        ansi_string.push('\n');
+    }
+
+    io::stdout().write_all(ansi_string.as_bytes())?;
+    io::stdout().flush()
+}
+
+fn print(printer: Printer, style: &mut Style<'_>, newline: bool) -> io::Result<()> {
+    match (printer, newline) {
+        (Printer::Baseline, _) => print_baseline(style, newline),
+        (Printer::Policy(_), false) => style.print(),
+        (Printer::Policy(_), true) => style.println(),
+    }
+}
+
+// Prints a screen of colored cells, one print call per cell.
+fn draw(printer: Printer) -> Duration {
+    if let Printer::Policy(policy) = printer {
+        Term::set_flush_policy(policy);
+    }
+
+    let start = Instant::now();
+
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let shade = u8::try_from((row + col) % 256).unwrap_or(0);
+            let mut cell = Style::this("#").fg_rgb(shade, 255 - shade, 128);
+
+            print(printer, &mut cell, false).expect("printing failed");
+        }
+
+        print(printer, &mut Style::this("|"), true).expect("printing failed");
+    }
+
+    Term::flush_output().expect("flushing failed");
+    start.elapsed()
+}
+
+fn main() {
+    // Render the escape sequences even though stdout is redirected.
+    Term::set_color_policy(ColorPolicy::Always);
+
+    let cells = f64::from(ROWS * (COLS + 1));
+
+    for (name, printer) in [
+        ("previous behavior", Printer::Baseline),
+        ("per call", Printer::Policy(FlushPolicy::PerCall)),
+        ("newline", Printer::Policy(FlushPolicy::Newline)),
+        ("manual", Printer::Policy(FlushPolicy::Manual)),
+    ] {
+        let elapsed = draw(printer);
+        let per_cell = elapsed.as_secs_f64() * 1e9 / cells;
+
+        eprintln!("{name:>17}: {elapsed:>10.2?} ({per_cell:.0} ns per cell)");
+    }
+}
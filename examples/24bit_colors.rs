@@ -1,25 +1,27 @@
 use std::io;
 
-use term_mods::{Style, Term};
+use term_mods::{Command, CommandBuffer, Style, Term};
 
 fn main() -> io::Result<()> {
     // Prints a full screen 24-bit spectral pattern.
     println!("24-bit RGB Colors:");
 
-    let (height, width) = Term::get_term_size();
+    let (height, width) = Term::get_term_size()?;
 
     let total_cols = u32::from(width) - 1;
     let total_rows = u32::from(height) - 2;
     let total_cells = total_cols * total_rows;
 
-    let symbols = vec!["/", "\\"];
+    let symbols = ["/", "\\"];
+
+    // Queue every cell and write the whole pattern at once.
+    let mut frame = CommandBuffer::with_capacity(total_cells as usize * 40);
 
     for cellnum in 0..total_cells {
         // Red -> u8
         let red = 255 - cellnum * 255 / total_cells;
         let Ok(red) = u8::try_from(red) else {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 "Error while parsing to a u8 integer."
             ));
         };
@@ -28,8 +30,7 @@ fn main() -> io::Result<()> {
         let green = cellnum * 510 / total_cells;
         let green = if green > 255 { 510 - green } else { green };
         let Ok(green) = u8::try_from(green) else {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 "Error while parsing to a u8 integer."
             ));
         };
@@ -37,8 +38,7 @@ fn main() -> io::Result<()> {
         // Blue -> u8
         let blue = cellnum * 255 / total_cells;
         let Ok(blue) = u8::try_from(blue) else {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 "Error while parsing to a u8 integer."
             ));
         };
@@ -46,17 +46,19 @@ fn main() -> io::Result<()> {
         let idx = cellnum as usize;
         let text = symbols[idx % 2];
 
-        // Print current cell to stdout
-        Style::this(text)
-            .bg_rgb(red, green, blue)
-            .fg_rgb(255 - red, 255 - green, 255 - blue)
-            .print()?;
+        // Queue the current cell
+        frame.queue(Command::PrintStyled(
+            Style::this(text)
+                .bg_rgb(red, green, blue)
+                .fg_rgb(255 - red, 255 - green, 255 - blue)
+        ))?;
 
         if (cellnum + 1) % total_cols == 0 {
-            println!();
+            frame.queue(Command::Print("\n"))?;
         }
     }
 
-    println!();
+    frame.queue(Command::Print("\n"))?;
+    frame.flush_to(&mut io::stdout())?;
     Ok(())
 }
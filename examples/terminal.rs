@@ -3,16 +3,15 @@ use std::io::{self, Write};
 use term_mods::{Term, Style};
 
 fn main() -> io::Result<()> {
-    let (height, width) = Term::get_term_size();
+    let (height, width) = Term::get_term_size()?;
 
     let mut stdout = io::stdout().lock();
 
     make_text_block(height - 1, width, &mut stdout)?;
 
-    let Ok(msg_len) = u8::try_from("| THIS IS A CENTERED TEXT MESSAGE |".len()) else {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Unable to parse a u8 integer from message length."
+    let Ok(msg_len) = u16::try_from("| THIS IS A CENTERED TEXT MESSAGE |".len()) else {
+        return Err(io::Error::other(
+            "Unable to parse a u16 integer from message length."
         ));
     };
 
@@ -31,7 +30,7 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn make_text_block<W: Write>(rows: u8, cols: u8, w: &mut W) -> io::Result<()> {
+fn make_text_block<W: Write>(rows: u16, cols: u16, w: &mut W) -> io::Result<()> {
     let mut grid = String::new();
 
     // Fill the screen with 'X's
@@ -1,51 +1,53 @@
+use std::fmt::Write;
+
 use crate::{Attr, Style};
 
-impl<'a> Style<'a> {
+impl Style<'_> {
     /// Makes the text bold.
     #[must_use]
-    pub fn bold(&mut self) -> Self {
+    pub const fn bold(&mut self) -> Self {
         self.attr = Some(Attr::Bold);
         *self
     }
 
     /// Makes the text faint.
     #[must_use]
-    pub fn faint(&mut self) -> Self {
+    pub const fn faint(&mut self) -> Self {
         self.attr = Some(Attr::Faint);
         *self
     }
 
     /// Makes the text italicized.
     #[must_use]
-    pub fn italic(&mut self) -> Self {
+    pub const fn italic(&mut self) -> Self {
         self.attr = Some(Attr::Italic);
         *self
     }
 
     /// Makes the text underlined.
     #[must_use]
-    pub fn underline(&mut self) -> Self {
+    pub const fn underline(&mut self) -> Self {
         self.attr = Some(Attr::Underline);
         *self
     }
 
     /// Inverts the text and background colors.
     #[must_use]
-    pub fn invert(&mut self) -> Self {
+    pub const fn invert(&mut self) -> Self {
         self.attr = Some(Attr::Invert);
         *self
     }
 
     /// Inverts the text and background colors.
     #[must_use]
-    pub fn strike(&mut self) -> Self {
+    pub const fn strike(&mut self) -> Self {
         self.attr = Some(Attr::Strike);
         *self
     }
 
     /// Hides the text
     #[must_use]
-    pub fn hide(&mut self) -> Self {
+    pub const fn hide(&mut self) -> Self {
         self.attr = Some(Attr::Hide);
         *self
     }
@@ -53,7 +55,7 @@ impl<'a> Style<'a> {
     // Handles attribute component of the ANSI string.
     pub fn get_attr_code(&mut self, ansi_str: &mut String) -> bool {
         if let Some(attr) = self.attr {
-            ansi_str.push_str(&format!("{attr}"));
+            let _ = write!(ansi_str, "{attr}");
             true
         } else {
             false
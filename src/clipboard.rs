@@ -0,0 +1,240 @@
+use std::{
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{query, Command, Passthrough, Selection, Term, OSC, ST};
+
+// GNU screen drops DCS strings longer than this.
+const SCREEN_CHUNK_LEN: usize = 768;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Passthrough {
+    /// Returns the passthrough needed by the multiplexer this process runs in,
+    /// detected from the `TMUX` and `STY` environment variables.
+    #[must_use]
+    pub fn detect() -> Self {
+        if env::var_os("TMUX").is_some() {
+            Self::Tmux
+        } else if env::var_os("STY").is_some() {
+            Self::Screen
+        } else {
+            Self::None
+        }
+    }
+
+    /// Wraps `seq` so that the multiplexer forwards it to the outer terminal.
+    ///
+    /// Sequences passed through screen should end with BEL rather than ST,
+    /// since screen ends the wrapper at the first ST.
+    #[must_use]
+    pub fn wrap(self, seq: &str) -> String {
+        match self {
+            Self::None => seq.to_string(),
+            Self::Tmux => format!("\x1bPtmux;{}{ST}", seq.replace('\x1b', "\x1b\x1b")),
+            Self::Screen => {
+                let mut wrapped = String::with_capacity(seq.len() + 8);
+                let mut rest = seq;
+
+                while !rest.is_empty() {
+                    // Chunks end early rather than split a character.
+                    let mut len = rest.len().min(SCREEN_CHUNK_LEN);
+
+                    while !rest.is_char_boundary(len) {
+                        len -= 1;
+                    }
+
+                    let (chunk, tail) = rest.split_at(len);
+                    let _ = write!(wrapped, "\x1bP{chunk}{ST}");
+                    rest = tail;
+                }
+
+                wrapped
+            },
+        }
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+impl Term {
+    /// Places `data` on the local clipboard or primary selection with OSC 52,
+    /// which also works over SSH.
+    ///
+    /// Terminals may ignore the request, and many limit how much data is
+    /// accepted. Inside tmux or screen, pass [`Passthrough::detect`] so that
+    /// the request reaches the outer terminal.
+    ///
+    /// ```no_run
+    /// use term_mods::{Passthrough, Selection, Term};
+    ///
+    /// let mut stdout = std::io::stdout();
+    /// Term::copy_to_clipboard(b"copied", Selection::Clipboard, Passthrough::detect(), &mut stdout)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn copy_to_clipboard<W: Write>(
+        data: &[u8],
+        selection: Selection,
+        passthrough: Passthrough,
+        w: &mut W
+    ) -> io::Result<()> {
+        Command::CopyToClipboard(data, selection, passthrough).write_to(w)
+    }
+
+    /// Reads the contents of the local clipboard or primary selection with
+    /// OSC 52.
+    ///
+    /// Returns `None` if the terminal does not answer, which most terminals do
+    /// unless reading the clipboard has been allowed in their settings. Inside
+    /// tmux, the answer comes from tmux's own buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal, it does not
+    /// answer within `timeout`, or its answer is not valid base64.
+    pub fn read_clipboard(selection: Selection, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let request = format!("{OSC}52;{selection};?{ST}{}", query::DA1);
+        let reply = query::query(&request, timeout, |reply| {
+            find_reply(reply).is_some() || query::has_da1(reply)
+        })?;
+
+        find_reply(&reply)
+            .map(|data| {
+                decode(data).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "The clipboard reply is not valid base64.")
+                })
+            })
+            .transpose()
+    }
+}
+
+impl std::fmt::Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Clipboard => "c",
+            Self::Primary => "p",
+        })
+    }
+}
+
+/// Builds the OSC 52 sequence that places `data` on the clipboard or primary
+/// selection, wrapped for `passthrough`.
+pub(crate) fn copy_sequence(data: &[u8], selection: Selection, passthrough: Passthrough) -> String {
+    passthrough.wrap(&format!("{OSC}52;{selection};{}\x07", encode(data)))
+}
+
+// Returns the base64 data of a complete OSC 52 reply in `reply`.
+fn find_reply(reply: &[u8]) -> Option<&[u8]> {
+    let body = query::find_osc(reply, b"52;")?;
+
+    // Skip the selection, which comes before the data.
+    Some(&body[body.iter().position(|b| *b == b';')? + 1..])
+}
+
+// Encodes `data` as padded standard base64.
+fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[(bits >> (18 - 6 * i)) as usize & 0x3f]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+// Decodes padded or unpadded standard base64, returning `None` if it is invalid.
+fn decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    let encoded = encoded.strip_suffix(b"==").or_else(|| encoded.strip_suffix(b"=")).unwrap_or(encoded);
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+
+    for chunk in encoded.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut bits = 0u32;
+
+        for (i, byte) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|b| b == byte)?;
+            bits |= u32::try_from(value).ok()? << (18 - 6 * i);
+        }
+
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, find_reply};
+    use crate::{Passthrough, Selection, Term};
+
+    #[test]
+    fn base64_round_trip() {
+        let cases: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob\xff", "Zm9vYv8="),
+        ];
+
+        for (data, encoded) in cases {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded.as_bytes()).as_deref(), Some(data));
+        }
+
+        assert_eq!(decode(b"Zm9vYv8"), Some(b"foob\xff".to_vec()));
+        assert_eq!(decode(b"Zm9v!"), None);
+        assert_eq!(decode(b"Zm9vY"), None);
+    }
+
+    #[test]
+    fn copies_to_clipboard() {
+        let mut out = Vec::new();
+        Term::copy_to_clipboard(b"hi", Selection::Primary, Passthrough::None, &mut out).unwrap();
+
+        assert_eq!(out, b"\x1b]52;p;aGk=\x07");
+    }
+
+    #[test]
+    fn wraps_for_tmux() {
+        assert_eq!(Passthrough::Tmux.wrap("\x1b]52;c;aGk=\x07"), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+
+    #[test]
+    fn wraps_for_screen_in_chunks() {
+        let seq = "a".repeat(800);
+        let wrapped = Passthrough::Screen.wrap(&seq);
+
+        assert_eq!(wrapped, format!("\x1bP{}\x1b\\\x1bP{}\x1b\\", "a".repeat(768), "a".repeat(32)));
+    }
+
+    #[test]
+    fn wraps_for_screen_between_characters() {
+        let seq = format!("a{}", "é".repeat(400));
+        let wrapped = Passthrough::Screen.wrap(&seq);
+
+        assert_eq!(wrapped, format!("\x1bPa{}\x1b\\\x1bP{}\x1b\\", "é".repeat(383), "é".repeat(17)));
+    }
+
+    #[test]
+    fn finds_clipboard_reply() {
+        assert_eq!(find_reply(b"\x1b]52;c;aGk=\x07"), Some(&b"aGk="[..]));
+        assert_eq!(find_reply(b"x\x1b]52;;aGk=\x1b\\\x1b[?62c"), Some(&b"aGk="[..]));
+        assert_eq!(find_reply(b"\x1b]52;c;aGk=\x1b"), None);
+        assert_eq!(find_reply(b"\x1b[?62c"), None);
+    }
+}
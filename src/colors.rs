@@ -1,20 +1,23 @@
-use crate::{Bg, CSI, Fg, Style};
+use std::fmt::{self, Write};
+
+use crate::{link, Bg, Fg, Sgr, Style, Term, CSI, SGR_MAX_LEN};
 
 impl<'a> Style<'a> {
     /// Constructs a default Style object.
     #[must_use]
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             attr: None,
             bg: None,
             fg: None,
             text: "",
+            link: None,
         }
     }
 
     /// Constructs the text object that will be stylized.
     #[must_use]
-    pub fn this(s: &'a str) -> Self {
+    pub const fn this(s: &'a str) -> Self {
         Self {
             text: s,
             ..Self::new()
@@ -30,119 +33,119 @@ impl<'a> Style<'a> {
     /// // Prints a red "Oh no!" to stdout with a newline.
     /// ```
     #[must_use]
-    pub fn red(&mut self) -> Self {
+    pub const fn red(&mut self) -> Self {
         self.fg = Some(Fg::Red);
         *self
     }
 
     /// Sets the text color to green.
     #[must_use]
-    pub fn green(&mut self) -> Self {
+    pub const fn green(&mut self) -> Self {
         self.fg = Some(Fg::Green);
         *self
     }
 
     /// Sets the text color to yellow.
     #[must_use]
-    pub fn yellow(&mut self) -> Self {
+    pub const fn yellow(&mut self) -> Self {
         self.fg = Some(Fg::Yellow);
         *self
     }
 
     /// Sets the text color to blue.
     #[must_use]
-    pub fn blue(&mut self) -> Self {
+    pub const fn blue(&mut self) -> Self {
         self.fg = Some(Fg::Blue);
         *self
     }
 
     /// Sets the text color to magenta.
     #[must_use]
-    pub fn magenta(&mut self) -> Self {
+    pub const fn magenta(&mut self) -> Self {
         self.fg = Some(Fg::Magenta);
         *self
     }
 
     /// Sets the text color to cyan.
     #[must_use]
-    pub fn cyan(&mut self) -> Self {
+    pub const fn cyan(&mut self) -> Self {
         self.fg = Some(Fg::Cyan);
         *self
     }
 
     /// Sets the text color to black.
     #[must_use]
-    pub fn black(&mut self) -> Self {
+    pub const fn black(&mut self) -> Self {
         self.fg = Some(Fg::Black);
         *self
     }
 
     /// Sets the text color to white.
     #[must_use]
-    pub fn white(&mut self) -> Self {
+    pub const fn white(&mut self) -> Self {
         self.fg = Some(Fg::White);
         *self
     }
 
     /// Sets the text color to bright red.
     #[must_use]
-    pub fn br_red(&mut self) -> Self {
+    pub const fn br_red(&mut self) -> Self {
         self.fg = Some(Fg::BrightRed);
         *self
     }
 
     /// Sets the text color to bright green.
     #[must_use]
-    pub fn br_green(&mut self) -> Self {
+    pub const fn br_green(&mut self) -> Self {
         self.fg = Some(Fg::BrightGreen);
         *self
     }
 
     /// Sets the text color to bright yellow.
     #[must_use]
-    pub fn br_yellow(&mut self) -> Self {
+    pub const fn br_yellow(&mut self) -> Self {
         self.fg = Some(Fg::BrightYellow);
         *self
     }
 
     /// Sets the text color to bright blue.
     #[must_use]
-    pub fn br_blue(&mut self) -> Self {
+    pub const fn br_blue(&mut self) -> Self {
         self.fg = Some(Fg::BrightBlue);
         *self
     }
 
     /// Sets the text color to bright magenta.
     #[must_use]
-    pub fn br_magenta(&mut self) -> Self {
+    pub const fn br_magenta(&mut self) -> Self {
         self.fg = Some(Fg::BrightMagenta);
         *self
     }
 
     /// Sets the text color to bright cyan.
     #[must_use]
-    pub fn br_cyan(&mut self) -> Self {
+    pub const fn br_cyan(&mut self) -> Self {
         self.fg = Some(Fg::BrightCyan);
         *self
     }
 
     /// Sets the text color to bright black.
     #[must_use]
-    pub fn br_black(&mut self) -> Self {
+    pub const fn br_black(&mut self) -> Self {
         self.fg = Some(Fg::BrightBlack);
         *self
     }
 
     /// Sets the text color to bright white.
     #[must_use]
-    pub fn br_white(&mut self) -> Self {
+    pub const fn br_white(&mut self) -> Self {
         self.fg = Some(Fg::BrightWhite);
         *self
     }
 
     /// Sets the text color using 256-color mode.
     #[must_use]
-    pub fn fg_256(&mut self, c: u8) -> Self {
+    pub const fn fg_256(&mut self, c: u8) -> Self {
         self.fg = Some(Fg::Color256(c));
         *self
     }
@@ -157,167 +160,137 @@ impl<'a> Style<'a> {
     /// // prints it to stdout with a newline.
     /// ```
     #[must_use]
-    pub fn fg_rgb(&mut self, r: u8, g: u8, b: u8) -> Self {
+    pub const fn fg_rgb(&mut self, r: u8, g: u8, b: u8) -> Self {
         self.fg = Some(Fg::Rgb(r, g, b));
         *self
     }
 
     /// Sets the background to red.
     #[must_use]
-    pub fn bg_red(&mut self) -> Self {
+    pub const fn bg_red(&mut self) -> Self {
         self.bg = Some(Bg::Red);
         *self
     }
 
     /// Sets the background to green.
     #[must_use]
-    pub fn bg_green(&mut self) -> Self {
+    pub const fn bg_green(&mut self) -> Self {
         self.bg = Some(Bg::Green);
         *self
     }
 
     /// Sets the background to yellow.
     #[must_use]
-    pub fn bg_yellow(&mut self) -> Self {
+    pub const fn bg_yellow(&mut self) -> Self {
         self.bg = Some(Bg::Yellow);
         *self
     }
 
     /// Sets the background to blue.
     #[must_use]
-    pub fn bg_blue(&mut self) -> Self {
+    pub const fn bg_blue(&mut self) -> Self {
         self.bg = Some(Bg::Blue);
         *self
     }
 
     /// Sets the background to magenta.
     #[must_use]
-    pub fn bg_magenta(&mut self) -> Self {
+    pub const fn bg_magenta(&mut self) -> Self {
         self.bg = Some(Bg::Magenta);
         *self
     }
 
     /// Sets the background to cyan.
     #[must_use]
-    pub fn bg_cyan(&mut self) -> Self {
+    pub const fn bg_cyan(&mut self) -> Self {
         self.bg = Some(Bg::Cyan);
         *self
     }
 
     /// Sets the background to black.
     #[must_use]
-    pub fn bg_black(&mut self) -> Self {
+    pub const fn bg_black(&mut self) -> Self {
         self.bg = Some(Bg::Black);
         *self
     }
 
     /// Sets the background to white.
     #[must_use]
-    pub fn bg_white(&mut self) -> Self {
+    pub const fn bg_white(&mut self) -> Self {
         self.bg = Some(Bg::White);
         *self
     }
 
     /// Sets the background to bright red.
     #[must_use]
-    pub fn bg_br_red(&mut self) -> Self {
+    pub const fn bg_br_red(&mut self) -> Self {
         self.bg = Some(Bg::BrightRed);
         *self
     }
 
     /// Sets the background to bright green.
     #[must_use]
-    pub fn bg_br_green(&mut self) -> Self {
+    pub const fn bg_br_green(&mut self) -> Self {
         self.bg = Some(Bg::BrightGreen);
         *self
     }
 
     /// Sets the background to bright yellow.
     #[must_use]
-    pub fn bg_br_yellow(&mut self) -> Self {
+    pub const fn bg_br_yellow(&mut self) -> Self {
         self.bg = Some(Bg::BrightYellow);
         *self
     }
 
     /// Sets the background to bright blue.
     #[must_use]
-    pub fn bg_br_blue(&mut self) -> Self {
+    pub const fn bg_br_blue(&mut self) -> Self {
         self.bg = Some(Bg::BrightBlue);
         *self
     }
 
     /// Sets the background to bright magenta.
     #[must_use]
-    pub fn bg_br_magenta(&mut self) -> Self {
+    pub const fn bg_br_magenta(&mut self) -> Self {
         self.bg = Some(Bg::BrightMagenta);
         *self
     }
 
     /// Sets the background to bright cyan.
     #[must_use]
-    pub fn bg_br_cyan(&mut self) -> Self {
+    pub const fn bg_br_cyan(&mut self) -> Self {
         self.bg = Some(Bg::BrightCyan);
         *self
     }
 
     /// Sets the background to bright black.
     #[must_use]
-    pub fn bg_br_black(&mut self) -> Self {
+    pub const fn bg_br_black(&mut self) -> Self {
         self.bg = Some(Bg::BrightBlack);
         *self
     }
 
     /// Sets the background to bright white.
     #[must_use]
-    pub fn bg_br_white(&mut self) -> Self {
+    pub const fn bg_br_white(&mut self) -> Self {
         self.bg = Some(Bg::BrightWhite);
         *self
     }
 
     /// Sets the background color using 256-color mode.
     #[must_use]
-    pub fn bg_256(&mut self, c: u8) -> Self {
+    pub const fn bg_256(&mut self, c: u8) -> Self {
         self.bg = Some(Bg::Color256(c));
         *self
     }
 
     /// Sets the background color using an RGB value.
     #[must_use]
-    pub fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> Self {
+    pub const fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> Self {
         self.bg = Some(Bg::Rgb(r, g, b));
         *self
     }
 
-    // Handles foreground component of the ANSI string.
-    fn get_fg_code(&mut self, ansi_str: &mut String) -> bool {
-        match (self.attr, self.fg) {
-            (_, None) => false,
-            (Some(_), Some(fg_color)) => {
-                ansi_str.push_str(&format!(";{fg_color}"));
-                true
-            },
-            (None, Some(fg_color)) => {
-                ansi_str.push_str(&format!("{fg_color}"));
-                true
-            }
-        }
-    }
-
-    // Handles background component of the ANSI string.
-    fn get_bg_code(&mut self, ansi_str: &mut String) -> bool {
-        match (self.attr, self.fg, self.bg) {
-            (_, _, None) => false,
-            (Some(_), _, Some(bg_color)) | (_, Some(_), Some(bg_color)) => {
-                ansi_str.push_str(&format!(";{bg_color}"));
-                true
-            },
-            (None, None, Some(bg_color)) => {
-                ansi_str.push_str(&format!("{bg_color}"));
-                true
-            }
-        }
-    }
-
     /// Builds and returns the ANSI string represented by the Style object.
     ///
     /// ```
@@ -328,21 +301,143 @@ impl<'a> Style<'a> {
     /// assert_eq!(ansi_string, "\x1b[92mI'm bright green!\x1b[0m".to_string());
     /// ```
     pub fn get_ansi(&mut self) -> String {
-        let mut ansi_str = CSI.to_string();
+        self.render(true, true)
+    }
 
-        let has_attr       = self.get_attr_code(&mut ansi_str);
-        let has_foreground = self.get_fg_code(&mut ansi_str);
-        let has_background = self.get_bg_code(&mut ansi_str);
+    /// Builds the SGR sequence that starts the style on the stack, without
+    /// allocating. The sequence is empty if no attribute or color is set.
+    ///
+    /// ```
+    /// use term_mods::Style;
+    ///
+    /// let sgr = Style::new().bold().fg_rgb(255, 128, 0).sgr();
+    ///
+    /// assert_eq!(sgr.as_str(), "\x1b[1;38;2;255;128;0m");
+    /// ```
+    #[must_use]
+    pub fn sgr(&self) -> Sgr {
+        let mut sgr = Sgr { buf: [0; SGR_MAX_LEN], len: 0 };
 
-        if has_attr || has_foreground || has_background {
-            format!("{ansi_str}m{}{CSI}0m", self.text)
-        } else {
-            self.text.to_string()
+        if self.attr.is_none() && self.fg.is_none() && self.bg.is_none() {
+            return sgr;
         }
+
+        let mut w = SgrWriter(&mut sgr);
+        let mut sep = "";
+        let _ = w.write_str(CSI);
+
+        if let Some(attr) = self.attr {
+            let _ = write!(w, "{attr}");
+            sep = ";";
+        }
+
+        if let Some(fg) = self.fg {
+            let _ = write!(w, "{sep}{fg}");
+            sep = ";";
+        }
+
+        if let Some(bg) = self.bg {
+            let _ = write!(w, "{sep}{bg}");
+        }
+
+        let _ = w.write_char('m');
+        sgr
+    }
+
+    /// Builds the styled text, or only the text if `colors` is false, wrapped
+    /// in its hyperlink if any.
+    pub(crate) fn render(&self, colors: bool, links: bool) -> String {
+        Styled { style: self, colors, links }.to_string()
+    }
+
+    /// Returns the styled text as it should be written to a stream, which is
+    /// a terminal if `is_terminal`, following the color policy and hyperlink
+    /// support.
+    pub(crate) fn styled_for(&self, is_terminal: bool) -> Styled<'_, 'a> {
+        Styled {
+            style: self,
+            colors: Term::color_policy().enabled(is_terminal),
+            links: self.link.is_some() && link::links_supported(is_terminal),
+        }
+    }
+}
+
+/// Styled text, written without allocating unless it is a hyperlink.
+pub(crate) struct Styled<'s, 'a> {
+    style: &'s Style<'a>,
+    colors: bool,
+    links: bool,
+}
+
+impl fmt::Display for Styled<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(target) = self.style.link else {
+            return write_sgr_text(self.style, self.colors, f);
+        };
+
+        link::write_link(f, target, self.links, |f| write_sgr_text(self.style, self.colors, f))
     }
 }
 
-impl<'a> Default for Style<'a> {
+// Writes the text wrapped in its SGR sequence, or only the text if `colors`
+// is false.
+fn write_sgr_text<W: fmt::Write>(style: &Style<'_>, colors: bool, w: &mut W) -> fmt::Result {
+    let sgr = if colors { style.sgr() } else { Sgr { buf: [0; SGR_MAX_LEN], len: 0 } };
+
+    if sgr.is_empty() {
+        return w.write_str(style.text);
+    }
+
+    w.write_str(sgr.as_str())?;
+    w.write_str(style.text)?;
+    write!(w, "{CSI}0m")
+}
+
+impl Sgr {
+    /// Returns the sequence.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(self.as_bytes()).unwrap_or_default()
+    }
+
+    /// Returns the bytes of the sequence.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns true if the style sets no attribute or color.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl fmt::Display for Sgr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Sgr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+// Appends to an `Sgr` buffer, failing if it is full.
+struct SgrWriter<'a>(&'a mut Sgr);
+
+impl fmt::Write for SgrWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.0.len + s.len();
+        self.0.buf.get_mut(self.0.len..end).ok_or(fmt::Error)?.copy_from_slice(s.as_bytes());
+        self.0.len = end;
+        Ok(())
+    }
+}
+
+impl Default for Style<'_> {
     fn default() -> Self {
         Self::new()
     }
@@ -361,6 +456,17 @@ mod tests {
         };
     }
 
+    #[test]
+    fn longest_sgr_fits() {
+        let sgr = Style::new().strike().fg_rgb(255, 255, 255).bg_rgb(255, 255, 255).sgr();
+        assert_eq!(sgr.as_str(), "\x1b[9;38;2;255;255;255;48;2;255;255;255m");
+    }
+
+    #[test]
+    fn empty_sgr_without_style() {
+        assert!(Style::this("X").sgr().is_empty());
+    }
+
     // Foreground color tests
     test_style!(black: Style::this("X").black() => "\x1b[30mX\x1b[0m");
     test_style!(red: Style::this("X").red() => "\x1b[31mX\x1b[0m");
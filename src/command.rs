@@ -0,0 +1,486 @@
+use std::{
+    fmt::{self, Write as _},
+    io::{self, Write},
+};
+
+use crate::{
+    clipboard, link, CursorShape, KittyFlags, Link, MouseEncoding, MouseMode, Passthrough, Rgb,
+    Selection, Style, Term, CSI, OSC, ST,
+};
+
+/// A terminal operation, or text to print, that can be queued in a
+/// [`CommandBuffer`], written at once with [`Term::execute`], or embedded in
+/// format strings through its [`Display`](fmt::Display) implementation.
+///
+/// Each operation matches the [`Term`] function of the same purpose.
+#[derive(Clone, Copy)]
+pub enum Command<'a> {
+    /// Prints text as is. See also [`Command::PrintStyled`].
+    Print(&'a str),
+    /// Prints styled text. See [`Style::write_to`].
+    PrintStyled(Style<'a>),
+    /// Scrolls the screen up. See [`Term::scroll_u`].
+    ScrollUp(u16),
+    /// Scrolls the screen down. See [`Term::scroll_d`].
+    ScrollDown(u16),
+    /// Sets the top and bottom scroll margins. See [`Term::set_scroll_region`].
+    SetScrollRegion(u16, u16),
+    /// Resets the scroll region. See [`Term::reset_scroll_region`].
+    ResetScrollRegion,
+    /// Enables left and right margins. See [`Term::enable_lr_margins`].
+    EnableLrMargins,
+    /// Disables left and right margins. See [`Term::disable_lr_margins`].
+    DisableLrMargins,
+    /// Sets the left and right margins. See [`Term::set_lr_margins`].
+    SetLrMargins(u16, u16),
+    /// Resets the left and right margins, or saves the cursor if they are
+    /// disabled. See [`Term::reset_lr_margins`].
+    ResetLrMargins,
+    /// Inserts blank lines. See [`Term::insert_ln`].
+    InsertLines(u16),
+    /// Deletes lines. See [`Term::delete_ln`].
+    DeleteLines(u16),
+    /// Inserts blank characters. See [`Term::insert_ch`].
+    InsertChars(u16),
+    /// Deletes characters. See [`Term::delete_ch`].
+    DeleteChars(u16),
+    /// Erases characters. See [`Term::erase_ch`].
+    EraseChars(u16),
+    /// Moves the cursor up one line, scrolling if needed. See [`Term::reverse_index`].
+    ReverseIndex,
+    /// Clears the screen. See [`Term::clr_scr`].
+    ClearScreen,
+    /// Clears the screen up to the cursor. See [`Term::clr_scr_to_start`].
+    ClearScreenToStart,
+    /// Clears the screen from the cursor. See [`Term::clr_scr_to_end`].
+    ClearScreenToEnd,
+    /// Clears the line. See [`Term::clr_ln`].
+    ClearLine,
+    /// Clears the line up to the cursor. See [`Term::clr_ln_to_start`].
+    ClearLineToStart,
+    /// Clears the line from the cursor. See [`Term::clr_ln_to_end`].
+    ClearLineToEnd,
+    /// Shows the cursor. See [`Term::show_cursor`].
+    ShowCursor,
+    /// Hides the cursor. See [`Term::hide_cursor`].
+    HideCursor,
+    /// Switches to the alternate screen. See [`Term::enter_alt_screen`].
+    EnterAltScreen,
+    /// Switches back to the main screen. See [`Term::leave_alt_screen`].
+    LeaveAltScreen,
+    /// Begins a synchronized update. See [`Term::begin_sync_update`].
+    BeginSyncUpdate,
+    /// Ends a synchronized update. See [`Term::end_sync_update`].
+    EndSyncUpdate,
+    /// Sets the cursor shape. See [`Term::set_cursor_shape`].
+    SetCursorShape(CursorShape),
+    /// Sets the cursor color. See [`Term::set_cursor_color`].
+    SetCursorColor(u8, u8, u8),
+    /// Resets the cursor color. See [`Term::reset_cursor_color`].
+    ResetCursorColor,
+    /// Sets the default foreground color. See [`Term::set_fg_color`].
+    SetFgColor(Rgb),
+    /// Sets the default background color. See [`Term::set_bg_color`].
+    SetBgColor(Rgb),
+    /// Sets the color of a palette entry. See [`Term::set_palette_color`].
+    SetPaletteColor(u8, Rgb),
+    /// Resets the default foreground color. See [`Term::reset_fg_color`].
+    ResetFgColor,
+    /// Resets the default background color. See [`Term::reset_bg_color`].
+    ResetBgColor,
+    /// Resets a palette entry. See [`Term::reset_palette_color`].
+    ResetPaletteColor(u8),
+    /// Resets every palette entry. See [`Term::reset_palette`].
+    ResetPalette,
+    /// Sets the window title. See [`Term::set_title`].
+    SetTitle(&'a str),
+    /// Sets the icon name. See [`Term::set_icon_name`].
+    SetIconName(&'a str),
+    /// Sets the window title and icon name. See [`Term::set_title_and_icon`].
+    SetTitleAndIcon(&'a str),
+    /// Saves the window title. See [`Term::push_title`].
+    PushTitle,
+    /// Restores the window title. See [`Term::pop_title`].
+    PopTitle,
+    /// Saves the cursor position. See [`Term::save_cursor`].
+    SaveCursor,
+    /// Restores the cursor position. See [`Term::restore_cursor`].
+    RestoreCursor,
+    /// Moves the cursor up. See [`Term::cursor_u`].
+    MoveUp(u16),
+    /// Moves the cursor down. See [`Term::cursor_d`].
+    MoveDown(u16),
+    /// Moves the cursor right. See [`Term::cursor_r`].
+    MoveRight(u16),
+    /// Moves the cursor left. See [`Term::cursor_l`].
+    MoveLeft(u16),
+    /// Moves the cursor to a column. See [`Term::cursor_col`].
+    MoveToColumn(u16),
+    /// Moves the cursor to a row and column. See [`Term::cursor_goto`].
+    MoveTo(u16, u16),
+    /// Moves the cursor to the bottom left of a screen with the given number
+    /// of rows. See [`Term::cursor_bl`].
+    MoveToBottomLeft(u16),
+    /// Moves the cursor to the top left. See [`Term::cursor_tl`].
+    MoveToTopLeft,
+    /// Enables mouse reporting. See [`Term::enable_mouse`].
+    EnableMouse(MouseMode, MouseEncoding),
+    /// Disables mouse reporting. See [`Term::disable_mouse`].
+    DisableMouse,
+    /// Enables bracketed paste. See [`Term::enable_bracketed_paste`].
+    EnableBracketedPaste,
+    /// Disables bracketed paste. See [`Term::disable_bracketed_paste`].
+    DisableBracketedPaste,
+    /// Enables focus reporting. See [`Term::enable_focus_reporting`].
+    EnableFocusReporting,
+    /// Disables focus reporting. See [`Term::disable_focus_reporting`].
+    DisableFocusReporting,
+    /// Pushes kitty keyboard flags. See [`Term::push_kitty_flags`].
+    PushKittyFlags(KittyFlags),
+    /// Pops kitty keyboard flags. See [`Term::pop_kitty_flags`].
+    PopKittyFlags(u16),
+    /// Places data on the clipboard or primary selection. See
+    /// [`Term::copy_to_clipboard`].
+    CopyToClipboard(&'a [u8], Selection, Passthrough),
+    /// Writes text as a hyperlink, or in the form set by
+    /// [`Term::set_link_fallback`] if the terminal is not expected to support
+    /// hyperlinks. See [`Term::hyperlink`].
+    Hyperlink {
+        /// The text to print.
+        text: &'a str,
+        /// The link target and ID.
+        link: Link<'a>,
+    },
+    /// Writes a message centered on a row of the given width. See
+    /// [`Term::write_centered`].
+    WriteCentered(u16, u16, &'a str),
+    /// Writes a message at a row and column. See [`Term::write`].
+    WriteAt(u16, u16, &'a str),
+}
+
+impl Command<'_> {
+    /// Writes the command to `w` without flushing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is invalid, such as a scroll region
+    /// that ends above its first row or a [`Command::WriteCentered`] message
+    /// that does not fit, or if writing to `w` fails.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.check()?;
+        write!(w, "{self}")
+    }
+
+    // Returns an error if the command's parameters are invalid.
+    fn check(&self) -> io::Result<()> {
+        let msg = match *self {
+            Self::SetScrollRegion(top, bottom) if top == 0 || top >= bottom => {
+                "The scroll region must start at row 1 or later and end below its first row."
+            },
+            Self::SetLrMargins(left, right) if left == 0 || left >= right => {
+                "The margins must start at column 1 or later and end after their first column."
+            },
+            Self::WriteCentered(_, width, msg) => match u16::try_from(msg.len()) {
+                Err(_) => "Unable to convert the message length to u16.",
+                Ok(len) if len > width => "Message cannot be longer than the screen width.",
+                Ok(_) => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+
+        Err(io::Error::new(io::ErrorKind::InvalidInput, msg))
+    }
+}
+
+/// Writes the escape sequence for the command, or the text to print.
+///
+/// Parameters are not checked, unlike with [`Command::write_to`].
+///
+/// ```
+/// use term_mods::Command::{ClearLine, MoveTo};
+///
+/// assert_eq!(format!("{}{}", MoveTo(3, 5), ClearLine), "\x1b[3;5H\x1b[2K");
+/// ```
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Print(text) => f.write_str(text),
+            Self::PrintStyled(style) if style.text.is_empty() => Ok(()),
+            Self::PrintStyled(style) => write!(f, "{}", style.styled_for(true)),
+            Self::ScrollUp(num) => write!(f, "{CSI}{num}S"),
+            Self::ScrollDown(num) => write!(f, "{CSI}{num}T"),
+            Self::SetScrollRegion(top, bottom) => write!(f, "{CSI}{top};{bottom}r"),
+            Self::ResetScrollRegion => write!(f, "{CSI}r"),
+            Self::EnableLrMargins => write!(f, "{CSI}?69h"),
+            Self::DisableLrMargins => write!(f, "{CSI}?69l"),
+            Self::SetLrMargins(left, right) => write!(f, "{CSI}{left};{right}s"),
+            Self::ResetLrMargins => write!(f, "{CSI}s"),
+            Self::InsertLines(num) => write!(f, "{CSI}{num}L"),
+            Self::DeleteLines(num) => write!(f, "{CSI}{num}M"),
+            Self::InsertChars(num) => write!(f, "{CSI}{num}@"),
+            Self::DeleteChars(num) => write!(f, "{CSI}{num}P"),
+            Self::EraseChars(num) => write!(f, "{CSI}{num}X"),
+            Self::ReverseIndex => f.write_str("\x1bM"),
+            Self::ClearScreen => write!(f, "{CSI}2J"),
+            Self::ClearScreenToStart => write!(f, "{CSI}1J"),
+            Self::ClearScreenToEnd => write!(f, "{CSI}0J"),
+            Self::ClearLine => write!(f, "{CSI}2K"),
+            Self::ClearLineToStart => write!(f, "{CSI}1K"),
+            Self::ClearLineToEnd => write!(f, "{CSI}0K"),
+            Self::ShowCursor => write!(f, "{CSI}?25h"),
+            Self::HideCursor => write!(f, "{CSI}?25l"),
+            Self::EnterAltScreen => write!(f, "{CSI}?1049h"),
+            Self::LeaveAltScreen => write!(f, "{CSI}?1049l"),
+            Self::BeginSyncUpdate => write!(f, "{CSI}?2026h"),
+            Self::EndSyncUpdate => write!(f, "{CSI}?2026l"),
+            Self::SetCursorShape(shape) => write!(f, "{CSI}{shape} q"),
+            Self::SetCursorColor(r, g, b) => write!(f, "{OSC}12;rgb:{r:02x}/{g:02x}/{b:02x}{ST}"),
+            Self::ResetCursorColor => write!(f, "{OSC}112{ST}"),
+            Self::SetFgColor(color) => write!(f, "{OSC}10;{color}{ST}"),
+            Self::SetBgColor(color) => write!(f, "{OSC}11;{color}{ST}"),
+            Self::SetPaletteColor(index, color) => write!(f, "{OSC}4;{index};{color}{ST}"),
+            Self::ResetFgColor => write!(f, "{OSC}110{ST}"),
+            Self::ResetBgColor => write!(f, "{OSC}111{ST}"),
+            Self::ResetPaletteColor(index) => write!(f, "{OSC}104;{index}{ST}"),
+            Self::ResetPalette => write!(f, "{OSC}104{ST}"),
+            Self::SetTitle(title) => write!(f, "{OSC}2;{}{ST}", Sanitized(title)),
+            Self::SetIconName(name) => write!(f, "{OSC}1;{}{ST}", Sanitized(name)),
+            Self::SetTitleAndIcon(title) => write!(f, "{OSC}0;{}{ST}", Sanitized(title)),
+            Self::PushTitle => write!(f, "{CSI}22;0t"),
+            Self::PopTitle => write!(f, "{CSI}23;0t"),
+            Self::SaveCursor => f.write_str("\x1b7"),
+            Self::RestoreCursor => f.write_str("\x1b8"),
+            Self::MoveUp(num) => write!(f, "{CSI}{num}A"),
+            Self::MoveDown(num) => write!(f, "{CSI}{num}B"),
+            Self::MoveRight(num) => write!(f, "{CSI}{num}C"),
+            Self::MoveLeft(num) => write!(f, "{CSI}{num}D"),
+            Self::MoveToColumn(col) => write!(f, "{CSI}{col}G"),
+            Self::MoveTo(row, col) => write!(f, "{CSI}{row};{col}H"),
+            Self::MoveToBottomLeft(rows) => write!(f, "{CSI}{rows};1H"),
+            Self::MoveToTopLeft => write!(f, "{CSI}1;1H"),
+            Self::EnableMouse(mode, encoding) => write!(f, "{CSI}?{mode}h{CSI}?{encoding}h"),
+            Self::DisableMouse => write!(
+                f,
+                "{CSI}?1016l{CSI}?1006l{CSI}?1003l{CSI}?1002l{CSI}?1000l{CSI}?9l"
+            ),
+            Self::EnableBracketedPaste => write!(f, "{CSI}?2004h"),
+            Self::DisableBracketedPaste => write!(f, "{CSI}?2004l"),
+            Self::EnableFocusReporting => write!(f, "{CSI}?1004h"),
+            Self::DisableFocusReporting => write!(f, "{CSI}?1004l"),
+            Self::PushKittyFlags(flags) => write!(f, "{CSI}>{}u", flags.bits()),
+            Self::PopKittyFlags(num) => write!(f, "{CSI}<{num}u"),
+            Self::CopyToClipboard(data, selection, passthrough) => {
+                f.write_str(&clipboard::copy_sequence(data, selection, passthrough))
+            },
+            Self::Hyperlink { text, link } => {
+                link::write_link(f, link, link::links_supported(true), |f| f.write_str(text))
+            },
+            Self::WriteCentered(row, width, msg) => {
+                let len = u16::try_from(msg.len()).unwrap_or(u16::MAX);
+                let col = (width / 2).saturating_sub(len / 2);
+                write!(f, "{CSI}{row};{col}H{msg}")
+            },
+            Self::WriteAt(row, col, msg) => write!(f, "{CSI}{row};{col}H{msg}"),
+        }
+    }
+}
+
+// Writes text without its control characters, so that text embedded in a
+// sequence cannot end it early or inject other sequences.
+struct Sanitized<'a>(&'a str);
+
+impl fmt::Display for Sanitized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.chars().filter(|c| !c.is_control()).try_for_each(|c| f.write_char(c))
+    }
+}
+
+/// A buffer that collects commands and writes them out in one go.
+///
+/// Queueing a command only encodes it into memory, so a whole frame can be
+/// built up and then sent to the terminal with a single write and flush.
+/// Other `Term` functions can also write into the buffer, since it
+/// implements [`Write`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::{Command, CommandBuffer, Style};
+///
+/// let mut frame = CommandBuffer::new();
+///
+/// for row in 1..=10 {
+///     frame
+///         .queue(Command::MoveTo(row, 1))?
+///         .queue(Command::PrintStyled(Style::this("Hello").green()))?;
+/// }
+///
+/// frame.flush_to(&mut std::io::stdout())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CommandBuffer {
+    buf: Vec<u8>,
+}
+
+impl CommandBuffer {
+    /// Creates an empty buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty buffer with room for `capacity` bytes.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { buf: Vec::with_capacity(capacity) }
+    }
+
+    /// Adds a command to the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is invalid, such as a
+    /// [`Command::WriteCentered`] message that does not fit. Nothing is
+    /// queued in that case.
+    pub fn queue(&mut self, cmd: Command<'_>) -> io::Result<&mut Self> {
+        cmd.write_to(&mut self.buf)?;
+        Ok(self)
+    }
+
+    /// Writes the queued commands to `w`, flushes it and empties the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to or flushing `w` fails. The buffer is
+    /// emptied either way.
+    pub fn flush_to<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        let result = w.write_all(&self.buf).and_then(|()| w.flush());
+        self.buf.clear();
+        result
+    }
+
+    /// Returns the encoded commands.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the number of queued bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns true if nothing is queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Discards the queued commands.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+}
+
+impl Write for CommandBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Term {
+    /// Writes a single command to `w` and flushes it.
+    ///
+    /// Use a [`CommandBuffer`] instead when writing many commands at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is invalid or writing to `w` fails.
+    pub fn execute<W: Write>(cmd: Command<'_>, w: &mut W) -> io::Result<()> {
+        cmd.write_to(w)?;
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, CommandBuffer};
+    use crate::{link::links_supported, Link, Style, Term};
+
+    #[test]
+    fn queues_commands() {
+        let mut buf = CommandBuffer::new();
+        buf.queue(Command::MoveTo(3, 5)).unwrap().queue(Command::ClearLine).unwrap();
+        buf.queue(Command::Print("hi")).unwrap();
+
+        assert_eq!(buf.as_bytes(), b"\x1b[3;5H\x1b[2Khi");
+    }
+
+    #[test]
+    fn queues_styled_text() {
+        let mut buf = CommandBuffer::new();
+        buf.queue(Command::PrintStyled(Style::this("X").red())).unwrap();
+        buf.queue(Command::PrintStyled(Style::this("").red())).unwrap();
+
+        assert_eq!(buf.as_bytes(), b"\x1b[31mX\x1b[0m");
+    }
+
+    #[test]
+    fn invalid_command_queues_nothing() {
+        let mut buf = CommandBuffer::new();
+
+        assert!(buf.queue(Command::WriteCentered(1, 2, "too long")).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn flush_empties_buffer() {
+        let mut buf = CommandBuffer::new();
+        let mut out = Vec::new();
+
+        buf.queue(Command::HideCursor).unwrap();
+        Term::cursor_tl(&mut buf).unwrap();
+        buf.flush_to(&mut out).unwrap();
+
+        assert_eq!(out, b"\x1b[?25l\x1b[1;1H");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn formats_commands() {
+        use Command::{ClearLine, MoveTo, SetTitle};
+
+        assert_eq!(format!("{}{}", MoveTo(3, 5), ClearLine), "\x1b[3;5H\x1b[2K");
+        assert_eq!(SetTitle("a\x07b").to_string(), "\x1b]2;ab\x1b\\");
+        assert_eq!(Command::WriteCentered(1, 10, "hi").to_string(), "\x1b[1;4Hhi");
+        assert_eq!(Command::ResetPalette.to_string(), "\x1b]104\x1b\\");
+    }
+
+    #[test]
+    fn formats_hyperlink() {
+        let link = Link::path(std::path::Path::new("/")).with_id("1");
+        let text = Command::Hyperlink { text: "root", link }.to_string();
+
+        if links_supported(true) {
+            assert!(text.starts_with("\x1b]8;id=1;file://"));
+            assert!(text.ends_with("/\x1b\\root\x1b]8;;\x1b\\"));
+        } else {
+            assert!(text.starts_with("root"));
+        }
+    }
+
+    #[test]
+    fn executes_command() {
+        let mut out = Vec::new();
+        Term::execute(Command::ScrollUp(2), &mut out).unwrap();
+
+        assert_eq!(out, b"\x1b[2S");
+    }
+}
@@ -0,0 +1,247 @@
+use std::{io, time::Duration};
+
+use crate::{input::Csi, query, DeviceAttrs, ModeState, SecondaryAttrs, Term, TermInfo, TermVersion};
+
+// Requests secondary device attributes.
+const DA2: &str = "\x1b[>c";
+
+// Requests the terminal name and version.
+const XTVERSION: &str = "\x1b[>q";
+
+impl DeviceAttrs {
+    /// Returns true if the terminal reports feature `code`.
+    #[must_use]
+    pub fn has(&self, code: u16) -> bool {
+        self.features.contains(&code)
+    }
+
+    /// Returns true if the terminal reports sixel graphics.
+    #[must_use]
+    pub fn sixel(&self) -> bool {
+        self.has(4)
+    }
+
+    /// Returns true if the terminal reports `ReGIS` graphics.
+    #[must_use]
+    pub fn regis(&self) -> bool {
+        self.has(3)
+    }
+
+    /// Returns true if the terminal reports ANSI color.
+    #[must_use]
+    pub fn ansi_color(&self) -> bool {
+        self.has(22)
+    }
+
+    /// Returns true if the terminal reports rectangular editing.
+    #[must_use]
+    pub fn rect_editing(&self) -> bool {
+        self.has(28)
+    }
+}
+
+impl ModeState {
+    /// Returns true if the terminal recognizes the mode, which is how support
+    /// for a mode is usually detected.
+    #[must_use]
+    pub fn is_supported(self) -> bool {
+        self != Self::NotRecognized
+    }
+
+    /// Returns true if the mode is set.
+    #[must_use]
+    pub fn is_set(self) -> bool {
+        matches!(self, Self::Set | Self::PermanentlySet)
+    }
+
+    /// Converts the state code of a DECRQM reply.
+    #[must_use]
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1 => Self::Set,
+            2 => Self::Reset,
+            3 => Self::PermanentlySet,
+            4 => Self::PermanentlyReset,
+            _ => Self::NotRecognized,
+        }
+    }
+}
+
+impl Term {
+    /// Asks the terminal for its name and version, and its primary and
+    /// secondary device attributes, in one round trip.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use term_mods::Term;
+    ///
+    /// let info = Term::query_info(Duration::from_millis(200))?;
+    ///
+    /// if let Some(version) = info.version {
+    ///     println!("Running in {}", version.name);
+    /// }
+    ///
+    /// println!("Sixel graphics: {}", info.primary.sixel());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal, it does not
+    /// answer within `timeout`, or its primary device attributes are invalid.
+    pub fn query_info(timeout: Duration) -> io::Result<TermInfo> {
+        let request = format!("{XTVERSION}{DA2}{}", query::DA1);
+        let reply = query::query(&request, timeout, query::has_da1)?;
+
+        Ok(TermInfo {
+            version: find_version(&reply),
+            primary: primary_attrs(&reply)?,
+            secondary: query::find_csi(&reply, secondary_attrs),
+        })
+    }
+
+    /// Asks the terminal for its primary device attributes (DA1).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal, it does not
+    /// answer within `timeout`, or its answer is invalid.
+    pub fn query_primary_attrs(timeout: Duration) -> io::Result<DeviceAttrs> {
+        let reply = query::query(query::DA1, timeout, query::has_da1)?;
+        primary_attrs(&reply)
+    }
+
+    /// Asks the terminal for its secondary device attributes (DA2).
+    ///
+    /// Returns `None` if the terminal does not answer DA2.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_secondary_attrs(timeout: Duration) -> io::Result<Option<SecondaryAttrs>> {
+        let reply = query::query(&format!("{DA2}{}", query::DA1), timeout, query::has_da1)?;
+        Ok(query::find_csi(&reply, secondary_attrs))
+    }
+
+    /// Asks the terminal for its name and version with XTVERSION.
+    ///
+    /// Returns `None` if the terminal does not answer XTVERSION.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_version(timeout: Duration) -> io::Result<Option<TermVersion>> {
+        let reply = query::query(&format!("{XTVERSION}{}", query::DA1), timeout, query::has_da1)?;
+        Ok(find_version(&reply))
+    }
+
+    /// Asks the terminal for the state of DEC private mode `mode`, such as
+    /// 2026 for synchronized output, with DECRQM.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_mode(mode: u16, timeout: Duration) -> io::Result<ModeState> {
+        Ok(query::decrqm(mode, timeout)?.map_or(ModeState::NotRecognized, ModeState::from_code))
+    }
+}
+
+// Parses the DA1 reply (`CSI ? level ; features... c`) in `reply`.
+fn primary_attrs(reply: &[u8]) -> io::Result<DeviceAttrs> {
+    query::find_csi(reply, |csi| {
+        if csi.private != Some(b'?') || csi.final_byte != b'c' {
+            return None;
+        }
+
+        let mut params = (0..).map_while(|idx| csi.sub_params(idx).next());
+        let level = std::str::from_utf8(params.next()?).ok()?.parse().ok()?;
+        let features = params.filter_map(|p| std::str::from_utf8(p).ok()?.parse().ok()).collect();
+
+        Some(DeviceAttrs { level, features })
+    })
+    .ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Invalid primary device attributes."
+    ))
+}
+
+// Parses a DA2 reply (`CSI > type ; version ; option c`).
+fn secondary_attrs(csi: &Csi<'_>) -> Option<SecondaryAttrs> {
+    if csi.private != Some(b'>') || csi.final_byte != b'c' {
+        return None;
+    }
+
+    Some(SecondaryAttrs {
+        terminal_type: csi.param(0)?,
+        version: csi.param(1).unwrap_or(0),
+        option: csi.param(2),
+    })
+}
+
+// Parses the XTVERSION reply (`DCS > | text ST`) in `reply`.
+fn find_version(reply: &[u8]) -> Option<TermVersion> {
+    let start = reply.windows(4).position(|w| w == b"\x1bP>|")? + 4;
+    let len = reply[start..].windows(2).position(|w| w == b"\x1b\\")?;
+    let text = String::from_utf8_lossy(&reply[start..start + len]);
+    let text = text.trim();
+
+    // Names are followed by the version in parentheses, as in `XTerm(388)`,
+    // or after a space, as in `WezTerm 20240203`.
+    let (name, version) = if let Some((name, rest)) = text.split_once('(') {
+        (name, Some(rest.trim_end_matches(')')))
+    } else if let Some((name, rest)) = text.split_once(' ') {
+        (name, Some(rest))
+    } else {
+        (text, None)
+    };
+
+    (!name.is_empty()).then(|| TermVersion {
+        name: name.trim().to_string(),
+        version: version.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_version, primary_attrs, secondary_attrs};
+    use crate::{query::find_csi, DeviceAttrs, ModeState, SecondaryAttrs, TermVersion};
+
+    #[test]
+    fn parses_primary_attrs() {
+        let attrs = primary_attrs(b"\x1b[>1;4000;0c\x1b[?65;1;4;22c").unwrap();
+
+        assert_eq!(attrs, DeviceAttrs { level: 65, features: vec![1, 4, 22] });
+        assert!(attrs.sixel() && attrs.ansi_color() && !attrs.regis());
+        assert!(primary_attrs(b"\x1b[>1;4000;0c").is_err());
+    }
+
+    #[test]
+    fn parses_secondary_attrs() {
+        let attrs = find_csi(b"\x1b[?62c\x1b[>41;388;0c", secondary_attrs);
+        assert_eq!(attrs, Some(SecondaryAttrs { terminal_type: 41, version: 388, option: Some(0) }));
+    }
+
+    #[test]
+    fn parses_version() {
+        let version = |name: &str, v: Option<&str>| Some(TermVersion {
+            name: name.to_string(),
+            version: v.map(str::to_string),
+        });
+
+        assert_eq!(find_version(b"\x1bP>|XTerm(388)\x1b\\\x1b[?62c"), version("XTerm", Some("388")));
+        assert_eq!(find_version(b"\x1bP>|WezTerm 20240203\x1b\\"), version("WezTerm", Some("20240203")));
+        assert_eq!(find_version(b"\x1bP>|foot\x1b\\"), version("foot", None));
+        assert_eq!(find_version(b"\x1bP>|kitty(0.31"), None);
+    }
+
+    #[test]
+    fn converts_mode_states() {
+        assert_eq!(ModeState::from_code(0), ModeState::NotRecognized);
+        assert!(ModeState::from_code(2).is_supported());
+        assert!(!ModeState::from_code(2).is_set());
+        assert!(ModeState::from_code(3).is_set());
+    }
+}
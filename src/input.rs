@@ -0,0 +1,1327 @@
+use std::{
+    io,
+    ops::{BitOr, BitOrAssign},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::sys;
+
+/// An input event read from the terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A key press.
+    Key(KeyEvent),
+    /// A mouse action, reported once mouse tracking has been enabled with
+    /// [`Term::enable_mouse`](crate::Term::enable_mouse).
+    Mouse(MouseEvent),
+    /// Text pasted while bracketed paste was enabled with
+    /// [`Term::enable_bracketed_paste`](crate::Term::enable_bracketed_paste).
+    ///
+    /// Line breaks are normalized to `"\n"`.
+    Paste(String),
+    /// The terminal window gained focus, reported once focus reporting has
+    /// been enabled with [`Term::enable_focus_reporting`](crate::Term::enable_focus_reporting).
+    FocusGained,
+    /// The terminal window lost focus.
+    FocusLost,
+}
+
+/// A key press together with the modifier keys that were held.
+///
+/// The kind, alternate keys and text are only reported by terminals that
+/// support the kitty keyboard protocol, once it has been enabled with
+/// [`Term::push_kitty_flags`](crate::Term::push_kitty_flags). Otherwise every
+/// event is a [`KeyKind::Press`] with no alternates or text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// The key that was pressed.
+    pub code: KeyCode,
+    /// The modifier keys that were held.
+    pub mods: Modifiers,
+    /// Whether the key was pressed, repeated or released.
+    pub kind: KeyKind,
+    /// The key as it would be with Shift held, if it differs.
+    pub shifted: Option<char>,
+    /// The key at the same position on a standard US layout, if it differs.
+    pub base: Option<char>,
+    /// The text the key would produce, if the terminal reported it.
+    pub text: Option<String>,
+}
+
+/// Kinds of key events.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyKind {
+    /// The key was pressed.
+    #[default]
+    Press,
+    /// The key is being held and auto-repeated.
+    Repeat,
+    /// The key was released.
+    Release,
+}
+
+/// Keys that can be decoded from terminal input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCode {
+    /// A printable character. Shifted letters arrive as their uppercase form.
+    Char(char),
+    /// Enter or Return
+    Enter,
+    /// Tab
+    Tab,
+    /// Shift+Tab, as reported by terminals that send a dedicated sequence.
+    BackTab,
+    /// Backspace
+    Backspace,
+    /// Escape
+    Esc,
+    /// Up arrow
+    Up,
+    /// Down arrow
+    Down,
+    /// Left arrow
+    Left,
+    /// Right arrow
+    Right,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page up
+    PageUp,
+    /// Page down
+    PageDown,
+    /// Insert
+    Insert,
+    /// Delete
+    Delete,
+    /// A function key, from F1 to F35.
+    ///
+    /// Terminals modelled on xterm report F13 to F24 as F1 to F12 with Shift
+    /// held, while VT220-style terminals send F13 to F20 directly. Keys beyond
+    /// F20 are only reported with the kitty keyboard protocol.
+    F(u8),
+    /// Caps Lock, only reported with the kitty keyboard protocol
+    CapsLock,
+    /// Scroll Lock, only reported with the kitty keyboard protocol
+    ScrollLock,
+    /// Num Lock, only reported with the kitty keyboard protocol
+    NumLock,
+    /// Print Screen, only reported with the kitty keyboard protocol
+    PrintScreen,
+    /// Pause, only reported with the kitty keyboard protocol
+    Pause,
+    /// Menu, only reported with the kitty keyboard protocol
+    Menu,
+}
+
+/// A mouse action and where it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// What the mouse did.
+    pub kind: MouseKind,
+    /// The 1-based row, or the pixel row with [`MouseEncoding::SgrPixels`].
+    ///
+    /// [`MouseEncoding::SgrPixels`]: crate::MouseEncoding::SgrPixels
+    pub row: u16,
+    /// The 1-based column, or the pixel column with [`MouseEncoding::SgrPixels`].
+    ///
+    /// [`MouseEncoding::SgrPixels`]: crate::MouseEncoding::SgrPixels
+    pub col: u16,
+    /// The modifier keys that were held.
+    pub mods: Modifiers,
+}
+
+/// Kinds of mouse actions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseKind {
+    /// A button was pressed.
+    Press(MouseButton),
+    /// A button was released.
+    Release(MouseButton),
+    /// The mouse moved while a button was held.
+    Drag(MouseButton),
+    /// The mouse moved with no button held.
+    Move,
+    /// The wheel was scrolled up.
+    ScrollUp,
+    /// The wheel was scrolled down.
+    ScrollDown,
+    /// The wheel was scrolled left.
+    ScrollLeft,
+    /// The wheel was scrolled right.
+    ScrollRight,
+}
+
+/// Mouse buttons
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    /// Left button
+    Left,
+    /// Middle button
+    Middle,
+    /// Right button
+    Right,
+    /// Back (thumb) button
+    Back,
+    /// Forward (thumb) button
+    Forward,
+}
+
+/// A set of modifier keys.
+///
+/// Modifiers are combined with `|` and tested with [`Modifiers::contains`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    /// No modifiers
+    pub const NONE: Self = Self(0);
+    /// Shift
+    pub const SHIFT: Self = Self(1);
+    /// Alt (or Option)
+    pub const ALT: Self = Self(1 << 1);
+    /// Control
+    pub const CTRL: Self = Self(1 << 2);
+    /// Super (or Meta, Command, Windows)
+    pub const SUPER: Self = Self(1 << 3);
+    /// Hyper, only reported with the kitty keyboard protocol
+    pub const HYPER: Self = Self(1 << 4);
+    /// Meta, only reported with the kitty keyboard protocol
+    pub const META: Self = Self(1 << 5);
+    /// Caps Lock is on, only reported with the kitty keyboard protocol
+    pub const CAPS_LOCK: Self = Self(1 << 6);
+    /// Num Lock is on, only reported with the kitty keyboard protocol
+    pub const NUM_LOCK: Self = Self(1 << 7);
+
+    /// Returns true if every modifier in `other` is also in `self`.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if no modifiers are set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Decodes the modifier parameter of an xterm-style sequence, which is one
+    // more than the bitmask of held modifiers.
+    fn from_param(param: Option<u16>) -> Self {
+        let mask = param.unwrap_or(1).saturating_sub(1);
+        Self(u8::try_from(mask).unwrap_or(u8::MAX))
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl KeyEvent {
+    /// Constructs a key event.
+    #[must_use]
+    pub const fn new(code: KeyCode, mods: Modifiers) -> Self {
+        Self {
+            code,
+            mods,
+            kind: KeyKind::Press,
+            shifted: None,
+            base: None,
+            text: None,
+        }
+    }
+}
+
+impl From<KeyCode> for KeyEvent {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, Modifiers::NONE)
+    }
+}
+
+/// Reads and decodes input events from the terminal.
+///
+/// Input is read from stdin when it is a terminal and from the controlling
+/// terminal otherwise. The terminal should be in raw or cbreak mode (see
+/// [`Term::enable_raw_mode`](crate::Term::enable_raw_mode)) so that keys are
+/// delivered as they are pressed.
+///
+/// Because Escape is also the first byte of every escape sequence, a lone
+/// Escape is only reported once no further bytes arrive within the escape
+/// timeout. An Escape followed by another key within the timeout is decoded
+/// as that key with Alt held.
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::{input::{Event, EventReader, KeyCode}, Term};
+///
+/// let _raw = Term::enable_raw_mode()?;
+/// let mut reader = EventReader::new();
+///
+/// loop {
+///     if let Event::Key(key) = reader.read()? {
+///         if key.code == KeyCode::Char('q') {
+///             break;
+///         }
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct EventReader {
+    // Bytes that have been read but not yet decoded.
+    buf: Vec<u8>,
+    // How long to wait for the rest of a sequence after a partial one.
+    esc_timeout: Duration,
+    // The largest paste, in bytes, that is delivered in full.
+    paste_limit: usize,
+    // Whether the rest of an oversized paste is being dropped.
+    skipping_paste: bool,
+    // How many bytes of a paste at the start of `buf` have been searched for
+    // its end, so that the search resumes there as more arrives.
+    paste_searched: usize,
+    // Whether iteration has ended after an error.
+    finished: bool,
+}
+
+impl EventReader {
+    /// The default time to wait for the rest of an escape sequence.
+    pub const DEFAULT_ESC_TIMEOUT: Duration = Duration::from_millis(50);
+
+    /// The default size limit for pasted text, in bytes.
+    pub const DEFAULT_PASTE_LIMIT: usize = 1 << 20;
+
+    /// Constructs a reader for terminal input.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            esc_timeout: Self::DEFAULT_ESC_TIMEOUT,
+            paste_limit: Self::DEFAULT_PASTE_LIMIT,
+            skipping_paste: false,
+            paste_searched: 0,
+            finished: false,
+        }
+    }
+
+    /// Sets how long to wait for the rest of an escape sequence before
+    /// treating the bytes received so far as individual keys.
+    ///
+    /// Longer timeouts are more reliable over slow connections, while shorter
+    /// ones make a lone Escape key feel more responsive.
+    #[must_use]
+    pub fn esc_timeout(mut self, timeout: Duration) -> Self {
+        self.esc_timeout = timeout;
+        self
+    }
+
+    /// Sets the largest paste, in bytes, that is delivered in full.
+    ///
+    /// Longer pastes are cut short at the limit and the rest of the pasted
+    /// text is dropped, so that runaway input cannot exhaust memory.
+    #[must_use]
+    pub fn paste_limit(mut self, bytes: usize) -> Self {
+        self.paste_limit = bytes;
+        self
+    }
+
+    /// Blocks until the next input event is available and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the terminal fails or input has ended.
+    pub fn read(&mut self) -> io::Result<Event> {
+        loop {
+            if let Some(event) = self.next_event(None)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the next input event.
+    ///
+    /// Returns `None` if no event arrives in time.
+    ///
+    /// To handle input and [`Term::on_resize`](crate::Term::on_resize)
+    /// notifications in one loop, poll with a short timeout and then check
+    /// [`ResizeEvents::try_recv`](crate::ResizeEvents::try_recv). A resize
+    /// signal may be delivered to another thread, so it does not reliably end
+    /// the wait early.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use term_mods::{input::EventReader, Term};
+    ///
+    /// let resizes = Term::on_resize()?;
+    /// let mut reader = EventReader::new();
+    ///
+    /// loop {
+    ///     if let Some(event) = reader.poll(Duration::from_millis(50))? {
+    ///         println!("{event:?}");
+    ///     }
+    ///
+    ///     if let Some(size) = resizes.try_recv() {
+    ///         println!("Resized to {} columns", size.cols);
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the terminal fails or input has ended.
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        self.next_event(Some(Instant::now() + timeout))
+    }
+
+    // Decodes the next event, reading more input until `deadline` if needed.
+    fn next_event(&mut self, deadline: Option<Instant>) -> io::Result<Option<Event>> {
+        // Whether the rest of a partial sequence may still arrive.
+        let mut more = true;
+
+        loop {
+            if self.skipping_paste {
+                self.skip_paste();
+            }
+
+            if self.buf.is_empty() || self.skipping_paste {
+                if !self.wait(deadline)? {
+                    return Ok(None);
+                }
+                continue;
+            }
+
+            let parsed = if self.paste_searched > 0 && !self.paste_ended() {
+                Parsed::Incomplete
+            } else {
+                parse(&self.buf, more)
+            };
+
+            match parsed {
+                Parsed::Event(event, len) => {
+                    self.buf.drain(..len);
+                    self.paste_searched = 0;
+                    return Ok(Some(self.limit_paste(event)));
+                },
+                Parsed::Skip(len) => {
+                    self.buf.drain(..len);
+                    more = true;
+                },
+                Parsed::Incomplete if self.buf.starts_with(PASTE_START) => {
+                    self.paste_searched = self.buf.len();
+
+                    if self.buf.len() - PASTE_START.len() > self.paste_limit {
+                        // Deliver the start of an oversized paste and drop
+                        // the rest of it as it arrives.
+                        let text = paste_text(&self.buf[PASTE_START.len()..]);
+                        self.buf.clear();
+                        self.skipping_paste = true;
+                        self.paste_searched = 0;
+                        return Ok(Some(self.limit_paste(Event::Paste(text))));
+                    }
+
+                    // A long paste can take a while to arrive, so wait for its
+                    // end for as long as the caller is willing to.
+                    if !self.wait(deadline)? {
+                        return Ok(None);
+                    }
+                },
+                Parsed::Incomplete => {
+                    // Wait briefly for the rest of the sequence before
+                    // decoding the bytes received so far on their own.
+                    more = sys::poll_input(Some(self.esc_timeout))?;
+
+                    if more {
+                        self.fill()?;
+                    }
+                },
+            }
+        }
+    }
+
+    // Waits until `deadline` for more input and reads it. Returns false if
+    // none arrived in time.
+    fn wait(&mut self, deadline: Option<Instant>) -> io::Result<bool> {
+        let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+
+        if !sys::poll_input(timeout)? {
+            return Ok(false);
+        }
+
+        self.fill()?;
+        Ok(true)
+    }
+
+    // Returns true if the end of the paste at the start of the buffer has
+    // arrived, searching only the bytes that arrived since the last search.
+    fn paste_ended(&mut self) -> bool {
+        // Back up in case the end marker was split across reads.
+        let start = self.paste_searched.saturating_sub(PASTE_END.len() - 1);
+        self.paste_searched = self.buf.len();
+
+        find(&self.buf[start..], PASTE_END).is_some()
+    }
+
+    // Drops buffered input up to and including the end of an oversized paste.
+    fn skip_paste(&mut self) {
+        if let Some(pos) = find(&self.buf, PASTE_END) {
+            self.buf.drain(..pos + PASTE_END.len());
+            self.skipping_paste = false;
+        } else {
+            // Keep enough bytes to recognize an end marker split across reads.
+            let len = self.buf.len().saturating_sub(PASTE_END.len() - 1);
+            self.buf.drain(..len);
+        }
+    }
+
+    // Cuts pasted text down to the paste limit.
+    fn limit_paste(&self, event: Event) -> Event {
+        match event {
+            Event::Paste(mut text) if text.len() > self.paste_limit => {
+                let mut len = self.paste_limit;
+
+                while !text.is_char_boundary(len) {
+                    len -= 1;
+                }
+
+                text.truncate(len);
+                Event::Paste(text)
+            },
+            event => event,
+        }
+    }
+
+    // Reads whatever input is available into the buffer.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 1024];
+        let len = sys::read_input(&mut chunk)?;
+
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Terminal input has ended."
+            ));
+        }
+
+        self.buf.extend_from_slice(&chunk[..len]);
+        Ok(())
+    }
+}
+
+impl Default for EventReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterating yields events until reading fails, such as when input ends.
+/// The error is yielded once, and the iterator then stops.
+impl Iterator for EventReader {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let event = self.read();
+        self.finished = event.is_err();
+        Some(event)
+    }
+}
+
+// Outcome of decoding the start of an input buffer.
+#[derive(Debug, PartialEq, Eq)]
+enum Parsed {
+    // An event and the number of bytes it was decoded from.
+    Event(Event, usize),
+    // A number of bytes that do not form an event and should be dropped.
+    Skip(usize),
+    // The buffer ends partway through a sequence.
+    Incomplete,
+}
+
+// Markers that bracketed paste mode puts around pasted text.
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+// Escape sequences longer than this are assumed to be garbage.
+const MAX_SEQ_LEN: usize = 64;
+
+const ESC: u8 = 0x1b;
+
+// Decodes the event at the start of `buf`, which must not be empty.
+//
+// If `more` is false, no further input is expected and a partial sequence is
+// decoded as whatever keys its bytes represent on their own.
+fn parse(buf: &[u8], more: bool) -> Parsed {
+    match buf[0] {
+        ESC => parse_esc(buf, more),
+        byte if byte.is_ascii() => key(control_key(byte), 1),
+        _ => parse_utf8(buf, more),
+    }
+}
+
+// Decodes input that starts with an Escape byte.
+fn parse_esc(buf: &[u8], more: bool) -> Parsed {
+    match buf.get(1) {
+        None if more => Parsed::Incomplete,
+        None => key(KeyCode::Esc.into(), 1),
+        Some(b'[') => with_alt_fallback(parse_csi(buf, more), buf, more),
+        Some(b'O') => with_alt_fallback(parse_ss3(buf, more), buf, more),
+        Some(_) => match parse(&buf[1..], more) {
+            // Escape followed by a key is how terminals report Alt+key.
+            Parsed::Event(Event::Key(mut key), len) => {
+                key.mods |= Modifiers::ALT;
+                Parsed::Event(Event::Key(key), len + 1)
+            },
+            Parsed::Event(_, _) | Parsed::Skip(_) => key(KeyCode::Esc.into(), 1),
+            Parsed::Incomplete => Parsed::Incomplete,
+        },
+    }
+}
+
+// Decodes a lone `ESC [` or `ESC O` as Alt+`[` or Alt+`O` once it is clear
+// that no sequence follows.
+fn with_alt_fallback(parsed: Parsed, buf: &[u8], more: bool) -> Parsed {
+    if !more && buf.len() == 2 {
+        let code = KeyCode::Char(char::from(buf[1]));
+        key(KeyEvent::new(code, Modifiers::ALT), 2)
+    } else {
+        parsed
+    }
+}
+
+// Decodes a single ASCII byte.
+fn control_key(byte: u8) -> KeyEvent {
+    let code = match byte {
+        b'\r' | b'\n' => KeyCode::Enter,
+        b'\t' => KeyCode::Tab,
+        0x7f | 0x08 => KeyCode::Backspace,
+        0x00 => return KeyEvent::new(KeyCode::Char(' '), Modifiers::CTRL),
+        0x01..=0x1a => {
+            let c = char::from(b'a' + byte - 1);
+            return KeyEvent::new(KeyCode::Char(c), Modifiers::CTRL);
+        },
+        0x1c..=0x1f => {
+            let c = char::from(b'4' + byte - 0x1c);
+            return KeyEvent::new(KeyCode::Char(c), Modifiers::CTRL);
+        },
+        _ => KeyCode::Char(char::from(byte)),
+    };
+
+    code.into()
+}
+
+// Decodes a multi-byte UTF-8 character.
+fn parse_utf8(buf: &[u8], more: bool) -> Parsed {
+    let len = match buf[0] {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Parsed::Skip(1),
+    };
+
+    // Drop only the lead byte of a broken sequence, keeping whatever follows.
+    let tail = &buf[1..buf.len().min(len)];
+    if !tail.iter().all(|&b| (0x80..=0xbf).contains(&b)) {
+        return Parsed::Skip(1);
+    }
+
+    let Some(bytes) = buf.get(..len) else {
+        return if more { Parsed::Incomplete } else { Parsed::Skip(1) };
+    };
+
+    match std::str::from_utf8(bytes).ok().and_then(|s| s.chars().next()) {
+        Some(c) => key(KeyCode::Char(c).into(), len),
+        None => Parsed::Skip(1),
+    }
+}
+
+// A parsed control sequence: `ESC [`, optional private marker, parameters,
+// intermediate bytes and a final byte.
+pub(crate) struct Csi<'a> {
+    // A leading `<`, `=`, `>` or `?` marker.
+    pub private: Option<u8>,
+    // Parameter bytes, without the private marker.
+    pub params: &'a [u8],
+    // Intermediate bytes between the parameters and the final byte.
+    pub intermediates: &'a [u8],
+    // The byte that ends the sequence.
+    pub final_byte: u8,
+}
+
+impl<'a> Csi<'a> {
+    // Splits the complete control sequence at the start of `buf`, if any.
+    pub fn split(buf: &'a [u8]) -> Option<Self> {
+        split_csi(buf, false).ok().map(|(csi, _)| csi)
+    }
+
+    // Returns the first sub-parameter of the parameter at `idx`, or `None` if
+    // it is missing, empty or not a number.
+    pub fn param(&self, idx: usize) -> Option<u16> {
+        self.sub_param(idx, 0)
+    }
+
+    // Returns sub-parameter `sub` of the parameter at `idx`, or `None` if it
+    // is missing, empty or not a number.
+    pub fn sub_param<T: FromStr>(&self, idx: usize, sub: usize) -> Option<T> {
+        self.sub_params(idx).nth(sub).and_then(parse_num)
+    }
+
+    // Returns the `:`-separated sub-parameters of the parameter at `idx`.
+    pub fn sub_params(&self, idx: usize) -> impl Iterator<Item = &'a [u8]> {
+        self.params
+            .split(|b| *b == b';')
+            .nth(idx)
+            .into_iter()
+            .flat_map(|param| param.split(|b| *b == b':'))
+    }
+}
+
+// Parses an unsigned decimal number.
+fn parse_num<T: FromStr>(bytes: &[u8]) -> Option<T> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+// Splits a control sequence at the start of `buf` into its parts, returning
+// the sequence and its length.
+fn split_csi(buf: &[u8], more: bool) -> Result<(Csi<'_>, usize), Parsed> {
+    let body = &buf[2..];
+
+    let Some(end) = body.iter().position(|b| (0x40..=0x7e).contains(b)) else {
+        return Err(if more && buf.len() < MAX_SEQ_LEN {
+            Parsed::Incomplete
+        } else {
+            Parsed::Skip(buf.len())
+        });
+    };
+
+    let (head, final_byte) = (&body[..end], body[end]);
+    let len = end + 3;
+
+    let private = match head.first() {
+        Some(b @ b'<'..=b'?') => Some(*b),
+        _ => None,
+    };
+
+    let head = &head[usize::from(private.is_some())..];
+    let split = head.iter().position(|b| (0x20..=0x2f).contains(b)).unwrap_or(head.len());
+    let (params, intermediates) = head.split_at(split);
+
+    if !params.iter().all(|b| (0x30..=0x3f).contains(b))
+        || !intermediates.iter().all(|b| (0x20..=0x2f).contains(b))
+    {
+        return Err(Parsed::Skip(len));
+    }
+
+    Ok((Csi { private, params, intermediates, final_byte }, len))
+}
+
+// Decodes a control sequence (`ESC [ ...`).
+fn parse_csi(buf: &[u8], more: bool) -> Parsed {
+    if buf.starts_with(PASTE_START) {
+        return parse_paste(buf, more);
+    }
+
+    // The Linux console reports F1 to F5 as `ESC [ [ A` to `ESC [ [ E`.
+    if buf.get(2) == Some(&b'[') {
+        return match buf.get(3) {
+            None if more => Parsed::Incomplete,
+            Some(b @ b'A'..=b'E') => key(KeyCode::F(b - b'A' + 1).into(), 4),
+            _ => Parsed::Skip(buf.len().min(4)),
+        };
+    }
+
+    let (csi, len) = match split_csi(buf, more) {
+        Ok(split) => split,
+        Err(parsed) => return parsed,
+    };
+
+    if csi.private == Some(b'<') && matches!(csi.final_byte, b'M' | b'm') {
+        return match sgr_mouse(&csi) {
+            Some(mouse) => Parsed::Event(Event::Mouse(mouse), len),
+            None => Parsed::Skip(len),
+        };
+    }
+
+    if csi.private.is_some() || !csi.intermediates.is_empty() {
+        return Parsed::Skip(len);
+    }
+
+    match (csi.final_byte, csi.params.is_empty()) {
+        (b'I', true) => return Parsed::Event(Event::FocusGained, len),
+        (b'O', true) => return Parsed::Event(Event::FocusLost, len),
+        _ => {},
+    }
+
+    // A cursor position report, `CSI row ; col R`, which is a late reply to
+    // `Term::cursor_pos` rather than F3. Modified F3 always has a first
+    // parameter of 1, so only a report for the first row is ambiguous.
+    let is_report = csi.param(0).is_some_and(|row| row != 1) && csi.param(1).is_some();
+
+    if csi.final_byte == b'R' && is_report {
+        return Parsed::Skip(len);
+    }
+
+    let mods = Modifiers::from_param(csi.param(1));
+
+    let code = match csi.final_byte {
+        b'u' => return kitty_key(&csi).map_or(Parsed::Skip(len), |k| key(k, len)),
+        b'~' => match csi.param(0).and_then(tilde_key) {
+            Some(code) => code,
+            None => return Parsed::Skip(len),
+        },
+        b'Z' => KeyCode::BackTab,
+        byte => match letter_key(byte) {
+            Some(code) => code,
+            None => return Parsed::Skip(len),
+        },
+    };
+
+    let mut key_event = KeyEvent::new(code, mods);
+
+    if code == KeyCode::BackTab {
+        key_event.mods |= Modifiers::SHIFT;
+    }
+
+    // With the kitty protocol, these keys keep their legacy encoding but may
+    // carry an event kind after the modifiers.
+    key_event.kind = key_kind(csi.sub_param(1, 1));
+    key(key_event, len)
+}
+
+// Decodes a kitty keyboard protocol key:
+// `CSI code:shifted:base ; modifiers:kind ; text u`.
+fn kitty_key(csi: &Csi<'_>) -> Option<KeyEvent> {
+    let code = kitty_code(csi.sub_param(0, 0)?)?;
+    let mods = Modifiers::from_param(csi.param(1));
+
+    let mut key_event = KeyEvent::new(code, mods);
+    key_event.kind = key_kind(csi.sub_param(1, 1));
+    key_event.shifted = csi.sub_param(0, 1).and_then(char::from_u32);
+    key_event.base = csi.sub_param(0, 2).and_then(char::from_u32);
+
+    let text: String = csi
+        .sub_params(2)
+        .filter_map(parse_num)
+        .filter_map(char::from_u32)
+        .collect();
+
+    key_event.text = (!text.is_empty()).then_some(text);
+    Some(key_event)
+}
+
+// Decodes the event kind sub-parameter of the kitty keyboard protocol.
+fn key_kind(kind: Option<u16>) -> KeyKind {
+    match kind {
+        Some(2) => KeyKind::Repeat,
+        Some(3) => KeyKind::Release,
+        _ => KeyKind::Press,
+    }
+}
+
+// Maps a kitty keyboard protocol key code to its key.
+fn kitty_code(code: u32) -> Option<KeyCode> {
+    let code = match code {
+        9 => KeyCode::Tab,
+        13 => KeyCode::Enter,
+        27 => KeyCode::Esc,
+        8 | 127 => KeyCode::Backspace,
+        57358 => KeyCode::CapsLock,
+        57359 => KeyCode::ScrollLock,
+        57360 => KeyCode::NumLock,
+        57361 => KeyCode::PrintScreen,
+        57362 => KeyCode::Pause,
+        57363 => KeyCode::Menu,
+        57376..=57398 => KeyCode::F(u8::try_from(code - 57363).ok()?),
+        57399..=57426 => keypad_key(code)?,
+        // Other private use codes are media and modifier keys.
+        0..=31 | 57344..=63743 => return None,
+        _ => KeyCode::Char(char::from_u32(code)?),
+    };
+
+    Some(code)
+}
+
+// Maps a kitty keypad key code to the key it stands for.
+fn keypad_key(code: u32) -> Option<KeyCode> {
+    let code = match code {
+        57399..=57408 => KeyCode::Char(char::from_digit(code - 57399, 10)?),
+        57409 => KeyCode::Char('.'),
+        57410 => KeyCode::Char('/'),
+        57411 => KeyCode::Char('*'),
+        57412 => KeyCode::Char('-'),
+        57413 => KeyCode::Char('+'),
+        57414 => KeyCode::Enter,
+        57415 => KeyCode::Char('='),
+        57416 => KeyCode::Char(','),
+        57417 => KeyCode::Left,
+        57418 => KeyCode::Right,
+        57419 => KeyCode::Up,
+        57420 => KeyCode::Down,
+        57421 => KeyCode::PageUp,
+        57422 => KeyCode::PageDown,
+        57423 => KeyCode::Home,
+        57424 => KeyCode::End,
+        57425 => KeyCode::Insert,
+        57426 => KeyCode::Delete,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+// Decodes text between bracketed paste markers.
+fn parse_paste(buf: &[u8], more: bool) -> Parsed {
+    let body = &buf[PASTE_START.len()..];
+
+    match find(body, PASTE_END) {
+        Some(end) => {
+            let len = PASTE_START.len() + end + PASTE_END.len();
+            Parsed::Event(Event::Paste(paste_text(&body[..end])), len)
+        },
+        None if more => Parsed::Incomplete,
+        None => Parsed::Event(Event::Paste(paste_text(body)), buf.len()),
+    }
+}
+
+// Converts pasted bytes to text with `"\n"` line breaks.
+fn paste_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// Decodes an SGR mouse report: `CSI < button ; col ; row M` for presses and
+// motion, with a final `m` for releases.
+fn sgr_mouse(csi: &Csi<'_>) -> Option<MouseEvent> {
+    let cb = csi.param(0)?;
+    let col = csi.param(1)?;
+    let row = csi.param(2)?;
+
+    let mut mods = Modifiers::NONE;
+
+    for (bit, modifier) in [(4, Modifiers::SHIFT), (8, Modifiers::ALT), (16, Modifiers::CTRL)] {
+        if cb & bit != 0 {
+            mods |= modifier;
+        }
+    }
+
+    let low = cb & 0b11;
+
+    let kind = if cb & 64 != 0 {
+        match low {
+            0 => MouseKind::ScrollUp,
+            1 => MouseKind::ScrollDown,
+            2 => MouseKind::ScrollLeft,
+            _ => MouseKind::ScrollRight,
+        }
+    } else if cb & 32 != 0 && low == 3 && cb & 128 == 0 {
+        MouseKind::Move
+    } else {
+        let button = match (cb & 128 != 0, low) {
+            (false, 0) => MouseButton::Left,
+            (false, 1) => MouseButton::Middle,
+            (false, 2) => MouseButton::Right,
+            (true, 0) => MouseButton::Back,
+            (true, 1) => MouseButton::Forward,
+            _ => return None,
+        };
+
+        if cb & 32 != 0 {
+            MouseKind::Drag(button)
+        } else if csi.final_byte == b'm' {
+            MouseKind::Release(button)
+        } else {
+            MouseKind::Press(button)
+        }
+    };
+
+    Some(MouseEvent { kind, row, col, mods })
+}
+
+// Decodes a single shift sequence (`ESC O ...`).
+fn parse_ss3(buf: &[u8], more: bool) -> Parsed {
+    // Some terminals insert a modifier parameter, as in `ESC O 5 P`.
+    let body = &buf[2..];
+    let digits = body.iter().take_while(|b| b.is_ascii_digit() || **b == b';').count();
+
+    let Some(&final_byte) = body.get(digits) else {
+        return if more { Parsed::Incomplete } else { Parsed::Skip(buf.len()) };
+    };
+
+    let len = digits + 3;
+    let param = body[..digits].rsplit(|b| *b == b';').next().and_then(parse_num);
+    let mods = Modifiers::from_param(param);
+
+    match letter_key(final_byte) {
+        Some(code) => key(KeyEvent::new(code, mods), len),
+        None if final_byte == b'M' => key(KeyEvent::new(KeyCode::Enter, mods), len),
+        None => Parsed::Skip(len),
+    }
+}
+
+// Maps the final byte of a cursor or function key sequence to its key.
+fn letter_key(byte: u8) -> Option<KeyCode> {
+    let code = match byte {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+// Maps the first parameter of a `CSI ... ~` sequence to its key. The legacy
+// encodings go no further than F20; F21 and up need the kitty protocol.
+fn tilde_key(param: u16) -> Option<KeyCode> {
+    let code = match param {
+        1 | 7 => KeyCode::Home,
+        2 => KeyCode::Insert,
+        3 => KeyCode::Delete,
+        4 | 8 => KeyCode::End,
+        5 => KeyCode::PageUp,
+        6 => KeyCode::PageDown,
+        11..=15 => fkey(param - 10),
+        17..=21 => fkey(param - 11),
+        23..=26 => fkey(param - 12),
+        28 | 29 => fkey(param - 13),
+        31..=34 => fkey(param - 14),
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+// Builds a function key code from a number that is known to be small.
+fn fkey(num: u16) -> KeyCode {
+    KeyCode::F(u8::try_from(num).unwrap_or(u8::MAX))
+}
+
+// Builds a key event result.
+fn key(key: KeyEvent, len: usize) -> Parsed {
+    Parsed::Event(Event::Key(key), len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse, Event, KeyCode, KeyEvent, KeyKind, Modifiers, MouseButton, MouseEvent, MouseKind,
+        Parsed,
+    };
+
+    macro_rules! test_key {
+        ($label:ident: $input:expr => $code:expr, $mods:expr) => {
+            #[test]
+            fn $label() {
+                let input: &[u8] = $input;
+                let expected = Event::Key(KeyEvent::new($code, $mods));
+                assert_eq!(parse(input, true), Parsed::Event(expected, input.len()));
+            }
+        };
+    }
+
+    const NONE: Modifiers = Modifiers::NONE;
+    const SHIFT: Modifiers = Modifiers::SHIFT;
+    const ALT: Modifiers = Modifiers::ALT;
+    const CTRL: Modifiers = Modifiers::CTRL;
+
+    // Single byte tests
+    test_key!(ascii: b"a" => KeyCode::Char('a'), NONE);
+    test_key!(upper: b"A" => KeyCode::Char('A'), NONE);
+    test_key!(enter_cr: b"\r" => KeyCode::Enter, NONE);
+    test_key!(enter_lf: b"\n" => KeyCode::Enter, NONE);
+    test_key!(tab: b"\t" => KeyCode::Tab, NONE);
+    test_key!(backspace: b"\x7f" => KeyCode::Backspace, NONE);
+    test_key!(ctrl_a: b"\x01" => KeyCode::Char('a'), CTRL);
+    test_key!(ctrl_space: b"\x00" => KeyCode::Char(' '), CTRL);
+    test_key!(ctrl_backslash: b"\x1c" => KeyCode::Char('4'), CTRL);
+
+    // UTF-8 tests
+    test_key!(utf8_2: "é".as_bytes() => KeyCode::Char('é'), NONE);
+    test_key!(utf8_3: "€".as_bytes() => KeyCode::Char('€'), NONE);
+    test_key!(utf8_4: "🦀".as_bytes() => KeyCode::Char('🦀'), NONE);
+
+    // CSI tests
+    test_key!(up: b"\x1b[A" => KeyCode::Up, NONE);
+    test_key!(down: b"\x1b[B" => KeyCode::Down, NONE);
+    test_key!(right: b"\x1b[C" => KeyCode::Right, NONE);
+    test_key!(left: b"\x1b[D" => KeyCode::Left, NONE);
+    test_key!(home: b"\x1b[H" => KeyCode::Home, NONE);
+    test_key!(end: b"\x1b[F" => KeyCode::End, NONE);
+    test_key!(home_tilde: b"\x1b[1~" => KeyCode::Home, NONE);
+    test_key!(insert: b"\x1b[2~" => KeyCode::Insert, NONE);
+    test_key!(delete: b"\x1b[3~" => KeyCode::Delete, NONE);
+    test_key!(end_tilde: b"\x1b[4~" => KeyCode::End, NONE);
+    test_key!(page_up: b"\x1b[5~" => KeyCode::PageUp, NONE);
+    test_key!(page_down: b"\x1b[6~" => KeyCode::PageDown, NONE);
+    test_key!(back_tab: b"\x1b[Z" => KeyCode::BackTab, SHIFT);
+    test_key!(ctrl_up: b"\x1b[1;5A" => KeyCode::Up, CTRL);
+    test_key!(ctrl_shift_left: b"\x1b[1;6D" => KeyCode::Left, CTRL | SHIFT);
+    test_key!(alt_delete: b"\x1b[3;3~" => KeyCode::Delete, ALT);
+
+    // Function key tests
+    test_key!(f1_ss3: b"\x1bOP" => KeyCode::F(1), NONE);
+    test_key!(f4_ss3: b"\x1bOS" => KeyCode::F(4), NONE);
+    test_key!(f1_linux: b"\x1b[[A" => KeyCode::F(1), NONE);
+    test_key!(f5_linux: b"\x1b[[E" => KeyCode::F(5), NONE);
+    test_key!(f1_csi: b"\x1b[1;2P" => KeyCode::F(1), SHIFT);
+    test_key!(f5: b"\x1b[15~" => KeyCode::F(5), NONE);
+    test_key!(f6: b"\x1b[17~" => KeyCode::F(6), NONE);
+    test_key!(f10: b"\x1b[21~" => KeyCode::F(10), NONE);
+    test_key!(f11: b"\x1b[23~" => KeyCode::F(11), NONE);
+    test_key!(f12_ctrl: b"\x1b[24;5~" => KeyCode::F(12), CTRL);
+    test_key!(f13: b"\x1b[25~" => KeyCode::F(13), NONE);
+    test_key!(f16: b"\x1b[29~" => KeyCode::F(16), NONE);
+    test_key!(f20: b"\x1b[34~" => KeyCode::F(20), NONE);
+
+    // SS3 tests
+    test_key!(up_ss3: b"\x1bOA" => KeyCode::Up, NONE);
+    test_key!(home_ss3: b"\x1bOH" => KeyCode::Home, NONE);
+    test_key!(enter_keypad: b"\x1bOM" => KeyCode::Enter, NONE);
+    test_key!(ctrl_f1_ss3: b"\x1bO5P" => KeyCode::F(1), CTRL);
+
+    // Alt tests
+    test_key!(alt_a: b"\x1ba" => KeyCode::Char('a'), ALT);
+    test_key!(alt_ctrl_a: b"\x1b\x01" => KeyCode::Char('a'), ALT | CTRL);
+    test_key!(alt_utf8: "\x1bé".as_bytes() => KeyCode::Char('é'), ALT);
+    test_key!(alt_up: b"\x1b\x1b[A" => KeyCode::Up, ALT);
+
+    // Kitty keyboard protocol tests
+    test_key!(kitty_ctrl_i: b"\x1b[105;5u" => KeyCode::Char('i'), CTRL);
+    test_key!(kitty_tab: b"\x1b[9u" => KeyCode::Tab, NONE);
+    test_key!(kitty_enter: b"\x1b[13u" => KeyCode::Enter, NONE);
+    test_key!(kitty_esc: b"\x1b[27u" => KeyCode::Esc, NONE);
+    test_key!(kitty_backspace: b"\x1b[127;3u" => KeyCode::Backspace, ALT);
+    test_key!(kitty_f13: b"\x1b[57376u" => KeyCode::F(13), NONE);
+    test_key!(kitty_f21: b"\x1b[57384u" => KeyCode::F(21), NONE);
+    test_key!(kitty_f24: b"\x1b[57387u" => KeyCode::F(24), NONE);
+    test_key!(kitty_f35: b"\x1b[57398u" => KeyCode::F(35), NONE);
+    test_key!(kitty_caps_lock: b"\x1b[57358u" => KeyCode::CapsLock, NONE);
+    test_key!(kitty_keypad_0: b"\x1b[57399u" => KeyCode::Char('0'), NONE);
+    test_key!(kitty_keypad_enter: b"\x1b[57414u" => KeyCode::Enter, NONE);
+    test_key!(
+        kitty_locks:
+        b"\x1b[97;193u" => KeyCode::Char('a'), Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK
+    );
+
+    fn kitty_event(input: &[u8]) -> KeyEvent {
+        match parse(input, true) {
+            Parsed::Event(Event::Key(key), len) if len == input.len() => key,
+            other => panic!("unexpected parse result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn kitty_event_kinds() {
+        assert_eq!(kitty_event(b"\x1b[97u").kind, KeyKind::Press);
+        assert_eq!(kitty_event(b"\x1b[97;1:1u").kind, KeyKind::Press);
+        assert_eq!(kitty_event(b"\x1b[97;1:2u").kind, KeyKind::Repeat);
+        assert_eq!(kitty_event(b"\x1b[97;1:3u").kind, KeyKind::Release);
+
+        let up = kitty_event(b"\x1b[1;5:3A");
+        assert_eq!((up.code, up.mods, up.kind), (KeyCode::Up, CTRL, KeyKind::Release));
+
+        let delete = kitty_event(b"\x1b[3;1:2~");
+        assert_eq!((delete.code, delete.kind), (KeyCode::Delete, KeyKind::Repeat));
+    }
+
+    #[test]
+    fn kitty_alternates_and_text() {
+        let shifted = kitty_event(b"\x1b[97:65;2;65u");
+        assert_eq!(shifted.code, KeyCode::Char('a'));
+        assert_eq!(shifted.mods, SHIFT);
+        assert_eq!(shifted.shifted, Some('A'));
+        assert_eq!(shifted.base, None);
+        assert_eq!(shifted.text.as_deref(), Some("A"));
+
+        let cyrillic = kitty_event("\x1b[1089::99;5u".as_bytes());
+        assert_eq!(cyrillic.code, KeyCode::Char('с'));
+        assert_eq!(cyrillic.shifted, None);
+        assert_eq!(cyrillic.base, Some('c'));
+        assert_eq!(cyrillic.text, None);
+
+        let composed = kitty_event(b"\x1b[101;;101:769u");
+        assert_eq!(composed.text.as_deref(), Some("e\u{301}"));
+    }
+
+    #[test]
+    fn kitty_unknown_keys_are_skipped() {
+        // Left Shift and a media key
+        assert_eq!(parse(b"\x1b[57441u", true), Parsed::Skip(8));
+        assert_eq!(parse(b"\x1b[57428u", true), Parsed::Skip(8));
+        // A reply to a flags query
+        assert_eq!(parse(b"\x1b[?15u", true), Parsed::Skip(6));
+    }
+
+    macro_rules! test_mouse {
+        ($label:ident: $input:expr => $kind:expr, $row:literal, $col:literal, $mods:expr) => {
+            #[test]
+            fn $label() {
+                let input: &[u8] = $input;
+                let mouse = MouseEvent { kind: $kind, row: $row, col: $col, mods: $mods };
+                assert_eq!(parse(input, true), Parsed::Event(Event::Mouse(mouse), input.len()));
+            }
+        };
+    }
+
+    // Mouse tests
+    test_mouse!(left_press: b"\x1b[<0;12;3M" => MouseKind::Press(MouseButton::Left), 3, 12, NONE);
+    test_mouse!(left_release: b"\x1b[<0;12;3m" => MouseKind::Release(MouseButton::Left), 3, 12, NONE);
+    test_mouse!(middle_press: b"\x1b[<1;1;1M" => MouseKind::Press(MouseButton::Middle), 1, 1, NONE);
+    test_mouse!(right_press: b"\x1b[<2;300;80M" => MouseKind::Press(MouseButton::Right), 80, 300, NONE);
+    test_mouse!(left_drag: b"\x1b[<32;5;6M" => MouseKind::Drag(MouseButton::Left), 6, 5, NONE);
+    test_mouse!(mouse_move: b"\x1b[<35;5;6M" => MouseKind::Move, 6, 5, NONE);
+    test_mouse!(scroll_up: b"\x1b[<64;2;2M" => MouseKind::ScrollUp, 2, 2, NONE);
+    test_mouse!(scroll_down: b"\x1b[<65;2;2M" => MouseKind::ScrollDown, 2, 2, NONE);
+    test_mouse!(scroll_left: b"\x1b[<66;2;2M" => MouseKind::ScrollLeft, 2, 2, NONE);
+    test_mouse!(scroll_right: b"\x1b[<67;2;2M" => MouseKind::ScrollRight, 2, 2, NONE);
+    test_mouse!(back_press: b"\x1b[<128;4;4M" => MouseKind::Press(MouseButton::Back), 4, 4, NONE);
+    test_mouse!(forward_press: b"\x1b[<129;4;4M" => MouseKind::Press(MouseButton::Forward), 4, 4, NONE);
+    test_mouse!(
+        ctrl_shift_click:
+        b"\x1b[<20;7;8M" => MouseKind::Press(MouseButton::Left), 8, 7, CTRL | SHIFT
+    );
+    test_mouse!(alt_scroll: b"\x1b[<73;9;9M" => MouseKind::ScrollDown, 9, 9, ALT);
+    test_mouse!(
+        pixel_coords:
+        b"\x1b[<0;1500;900M" => MouseKind::Press(MouseButton::Left), 900, 1500, NONE
+    );
+
+    #[test]
+    fn bad_mouse_report_is_skipped() {
+        assert_eq!(parse(b"\x1b[<0;12M", true), Parsed::Skip(8));
+        assert_eq!(parse(b"\x1b[<3;1;1M", true), Parsed::Skip(9));
+    }
+
+    // Focus tests
+    #[test]
+    fn focus() {
+        assert_eq!(parse(b"\x1b[I", true), Parsed::Event(Event::FocusGained, 3));
+        assert_eq!(parse(b"\x1b[O", true), Parsed::Event(Event::FocusLost, 3));
+        assert_eq!(parse(b"\x1b[1I", true), Parsed::Skip(4));
+    }
+
+    // Bracketed paste tests
+    #[test]
+    fn paste() {
+        let input = b"\x1b[200~line 1\r\nline 2\rline 3\x1b[201~x";
+        let paste = Event::Paste("line 1\nline 2\nline 3".to_string());
+        assert_eq!(parse(input, true), Parsed::Event(paste, input.len() - 1));
+    }
+
+    #[test]
+    fn paste_with_escapes() {
+        let input = "\x1b[200~\x1b[A€\t\x1b[201~".as_bytes();
+        let paste = Event::Paste("\x1b[A€\t".to_string());
+        assert_eq!(parse(input, true), Parsed::Event(paste, input.len()));
+    }
+
+    #[test]
+    fn partial_paste() {
+        assert_eq!(parse(b"\x1b[200~abc", true), Parsed::Incomplete);
+        assert_eq!(parse(b"\x1b[200~abc\x1b[201", true), Parsed::Incomplete);
+
+        let paste = Event::Paste("abc".to_string());
+        assert_eq!(parse(b"\x1b[200~abc", false), Parsed::Event(paste, 9));
+    }
+
+    #[test]
+    fn paste_end_search_resumes() {
+        let mut reader = super::EventReader::new();
+        reader.buf = b"\x1b[200~abc\x1b[20".to_vec();
+        reader.paste_searched = 6;
+
+        assert!(!reader.paste_ended());
+        assert_eq!(reader.paste_searched, reader.buf.len());
+
+        reader.buf.extend_from_slice(b"1~");
+        assert!(reader.paste_ended());
+    }
+
+    #[test]
+    fn paste_limit() {
+        let reader = super::EventReader::new().paste_limit(4);
+        let limited = reader.limit_paste(Event::Paste("abc€".to_string()));
+        assert_eq!(limited, Event::Paste("abc".to_string()));
+    }
+
+    #[test]
+    fn lone_esc_waits_for_more() {
+        assert_eq!(parse(b"\x1b", true), Parsed::Incomplete);
+
+        let esc = Event::Key(KeyCode::Esc.into());
+        assert_eq!(parse(b"\x1b", false), Parsed::Event(esc, 1));
+    }
+
+    #[test]
+    fn partial_sequences() {
+        assert_eq!(parse(b"\x1b[", true), Parsed::Incomplete);
+        assert_eq!(parse(b"\x1b[1;5", true), Parsed::Incomplete);
+        assert_eq!(parse(b"\x1bO", true), Parsed::Incomplete);
+        assert_eq!(parse("€".as_bytes().get(..2).unwrap(), true), Parsed::Incomplete);
+        assert_eq!(parse(b"\x1b[1;5", false), Parsed::Skip(5));
+    }
+
+    #[test]
+    fn partial_sequence_as_alt_key() {
+        let alt_bracket = KeyEvent::new(KeyCode::Char('['), ALT);
+        assert_eq!(parse(b"\x1b[", false), Parsed::Event(Event::Key(alt_bracket), 2));
+
+        let alt_o = KeyEvent::new(KeyCode::Char('O'), ALT);
+        assert_eq!(parse(b"\x1bO", false), Parsed::Event(Event::Key(alt_o), 2));
+    }
+
+    #[test]
+    fn trailing_input_is_left() {
+        let up = Event::Key(KeyCode::Up.into());
+        assert_eq!(parse(b"\x1b[Aabc", true), Parsed::Event(up, 3));
+    }
+
+    #[test]
+    fn unknown_sequence_is_skipped() {
+        assert_eq!(parse(b"\x1b[99~x", true), Parsed::Skip(5));
+        assert_eq!(parse(b"\x1b[1;2y", true), Parsed::Skip(6));
+        assert_eq!(parse(b"\xff", true), Parsed::Skip(1));
+    }
+
+    #[test]
+    fn cursor_report_is_skipped() {
+        assert_eq!(parse(b"\x1b[12;40R", true), Parsed::Skip(8));
+        assert_eq!(parse(b"\x1b[2;1R", true), Parsed::Skip(6));
+
+        let ctrl_f3 = KeyEvent::new(KeyCode::F(3), CTRL);
+        assert_eq!(parse(b"\x1b[1;5R", true), Parsed::Event(Event::Key(ctrl_f3), 6));
+    }
+
+    #[test]
+    fn broken_utf8_skips_lead_byte() {
+        assert_eq!(parse(b"\xe2a", true), Parsed::Skip(1));
+        assert_eq!(parse(b"\xe2a", false), Parsed::Skip(1));
+        assert_eq!(parse(b"\xe2\x82", false), Parsed::Skip(1));
+        assert_eq!(parse(b"\xe2\x82", true), Parsed::Incomplete);
+    }
+}
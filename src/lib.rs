@@ -19,10 +19,36 @@
 #![deny(clippy::pedantic)]
 #![deny(clippy::cargo)]
 
+// Defines a test that checks the sequence written by a `Term` function. It is
+// declared before the modules so that their tests can use it.
+#[cfg(test)]
+macro_rules! test_modifier {
+    ($label:ident: $modifier:ident($($arg:expr),*) => $ansi:literal) => {
+        #[test]
+        fn $label() {
+            let mut w = Vec::new();
+            $crate::Term::$modifier($($arg,)* &mut w).unwrap();
+            assert_eq!(String::from_utf8(w).unwrap(), $ansi);
+        }
+    };
+}
+
 pub mod attrs;
 pub mod colors;
 pub mod write;
 pub mod term;
+pub mod resize;
+pub mod raw;
+pub mod input;
+pub mod link;
+pub mod clipboard;
+pub mod screen;
+pub mod device;
+pub mod palette;
+pub mod command;
+
+mod query;
+mod sys;
 
 /// Primary structure for building a stylized string and printing it to stdout.
 ///
@@ -48,8 +74,92 @@ pub struct Style<'a> {
 
     // Text to be stylized.
     pub text: &'a str,
+
+    // Hyperlink target and ID, set with the link builders
+    link: Option<Link<'a>>,
+}
+
+/// When styled text is printed with its colors and attributes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorPolicy {
+    /// Style output to terminals, unless the `NO_COLOR` environment variable
+    /// is set or `TERM` is `dumb`. Setting `CLICOLOR_FORCE` styles all output.
+    /// The variables are read the first time they are needed, and whether
+    /// stdout and stderr are terminals is checked the first time each is
+    /// printed to.
+    #[default]
+    Auto,
+    /// Always style output
+    Always,
+    /// Never style output
+    Never,
+}
+
+/// When printed styled text is flushed to stdout or stderr
+///
+/// **Output held back under [`FlushPolicy::Newline`] or [`FlushPolicy::Manual`]
+/// is lost if the program exits without calling [`Term::flush_output`].**
+/// Nothing flushes it at exit, including returning from `main` and
+/// `std::process::exit`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Flush after every print
+    #[default]
+    PerCall,
+    /// Flush once a newline is printed
+    Newline,
+    /// Flush only when [`Term::flush_output`] is called, or once a large
+    /// amount of output is pending
+    Manual,
+}
+
+/// A hyperlink target, with an optional link ID
+///
+/// Created with [`Link::url`] or [`Link::path`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link<'a> {
+    // Where the link points
+    target: LinkTarget<'a>,
+
+    // Joins separately printed pieces of text with the same ID and target
+    // into one link
+    id: Option<&'a str>,
+}
+
+// Where a hyperlink points
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LinkTarget<'a> {
+    // A URL
+    Url(&'a str),
+    // A local file or directory, linked with a `file://` URI
+    Path(&'a std::path::Path),
+}
+
+/// How hyperlinks are printed when the output does not support them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkFallback {
+    /// Print only the text
+    #[default]
+    Text,
+    /// Print the text followed by the URL in parentheses
+    TextAndUrl,
+}
+
+/// The SGR sequence that starts a [`Style`], held in a fixed-size buffer so
+/// that building it never allocates.
+///
+/// Created by [`Style::sgr`].
+#[derive(Clone, Copy)]
+pub struct Sgr {
+    // Sequence bytes, of which the first `len` are used.
+    buf: [u8; SGR_MAX_LEN],
+    len: usize,
 }
 
+// Length of the longest SGR sequence a style produces: `CSI`, an attribute,
+// two RGB colors, two separators and the final `m`.
+const SGR_MAX_LEN: usize = 2 + 1 + 16 + 16 + 2 + 1;
+
 /// Foreground colors
 #[derive(Clone, Copy)]
 pub enum Fg {
@@ -154,5 +264,226 @@ pub enum Attr {
 /// Terminal manipulation methods and attributes.
 pub struct Term;
 
+/// Cursor shapes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CursorShape {
+    /// The terminal's configured default shape
+    #[default]
+    Default,
+    /// Blinking block
+    BlinkingBlock,
+    /// Steady block
+    SteadyBlock,
+    /// Blinking underline
+    BlinkingUnderline,
+    /// Steady underline
+    SteadyUnderline,
+    /// Blinking vertical bar
+    BlinkingBar,
+    /// Steady vertical bar
+    SteadyBar,
+}
+
+/// Selections that can be written with [`Term::copy_to_clipboard`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    /// The system clipboard
+    #[default]
+    Clipboard,
+    /// The primary selection, pasted with the middle mouse button on X11
+    Primary,
+}
+
+/// Wrappers that pass a control sequence through a terminal multiplexer to
+/// the terminal it runs in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Passthrough {
+    /// Write the sequence as is
+    #[default]
+    None,
+    /// Wrap the sequence in a tmux DCS passthrough
+    Tmux,
+    /// Wrap the sequence in GNU screen DCS strings
+    Screen,
+}
+
+/// Mouse tracking modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseMode {
+    /// Report button presses only
+    X10,
+    /// Report button presses and releases
+    Normal,
+    /// Also report motion while a button is held
+    ButtonEvent,
+    /// Also report all motion, even with no button held
+    AnyEvent,
+}
+
+/// Kitty keyboard protocol progressive enhancement flags.
+///
+/// Flags are combined with `|` and enabled with [`Term::push_kitty_flags`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KittyFlags(u8);
+
+impl KittyFlags {
+    /// Report keys that are ambiguous in the legacy encoding, such as Ctrl+I
+    /// and Tab, as distinct escape codes
+    pub const DISAMBIGUATE: Self = Self(1);
+    /// Report key repeat and release events
+    pub const REPORT_EVENTS: Self = Self(1 << 1);
+    /// Report the shifted and base layout keys
+    pub const REPORT_ALTERNATES: Self = Self(1 << 2);
+    /// Report every key, including Enter, Tab and Backspace, as an escape code
+    pub const REPORT_ALL_AS_ESCAPES: Self = Self(1 << 3);
+    /// Report the text each key produces
+    pub const REPORT_TEXT: Self = Self(1 << 4);
+
+    /// Returns true if every flag in `other` is also in `self`.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the flags as the bitmask used by the protocol.
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Constructs flags from the bitmask used by the protocol.
+    #[must_use]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & 0b1_1111)
+    }
+}
+
+impl std::ops::BitOr for KittyFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Mouse report encodings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseEncoding {
+    /// SGR encoding with 1-based cell coordinates
+    Sgr,
+    /// SGR encoding with pixel coordinates
+    SgrPixels,
+}
+
+pub use command::{Command, CommandBuffer};
+pub use raw::RawModeGuard;
+pub use resize::ResizeEvents;
+pub use screen::{AltScreenGuard, SyncUpdateGuard};
+pub use write::SharedStdout;
+
+/// Terminal window dimensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WinSize {
+    /// Number of rows (lines).
+    pub rows: u16,
+    /// Number of columns.
+    pub cols: u16,
+    /// Width of the window in pixels, if reported.
+    pub width_px: Option<u16>,
+    /// Height of the window in pixels, if reported.
+    pub height_px: Option<u16>,
+}
+
+/// A 24-bit color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    /// Red component.
+    pub r: u8,
+    /// Green component.
+    pub g: u8,
+    /// Blue component.
+    pub b: u8,
+}
+
+/// Terminal background brightness, which decides whether a dark or a light
+/// theme is readable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Background {
+    /// A dark background, suited to light text
+    Dark,
+    /// A light background, suited to dark text
+    Light,
+    /// The background could not be determined
+    #[default]
+    Unknown,
+}
+
+/// Primary device attributes (DA1), reported by every terminal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceAttrs {
+    /// Conformance level, such as 62 for a VT220 or 65 for a VT500.
+    pub level: u16,
+    /// Codes of the supported features, such as 4 for sixel graphics.
+    pub features: Vec<u16>,
+}
+
+/// Secondary device attributes (DA2).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SecondaryAttrs {
+    /// Terminal type code, such as 1 for a VT220 or 41 for a VT420.
+    pub terminal_type: u16,
+    /// Firmware or program version.
+    pub version: u16,
+    /// Keyboard or cartridge option, if reported.
+    pub option: Option<u16>,
+}
+
+/// Terminal name and version reported by XTVERSION.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermVersion {
+    /// Name of the terminal program, such as `XTerm` or `kitty`.
+    pub name: String,
+    /// Version of the terminal program, if reported.
+    pub version: Option<String>,
+}
+
+/// Everything the terminal reports about itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermInfo {
+    /// Name and version, if the terminal answers XTVERSION.
+    pub version: Option<TermVersion>,
+    /// Primary device attributes.
+    pub primary: DeviceAttrs,
+    /// Secondary device attributes, if the terminal answers DA2.
+    pub secondary: Option<SecondaryAttrs>,
+}
+
+/// Mode states reported by DECRQM
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModeState {
+    /// The terminal does not recognize the mode, or did not answer
+    #[default]
+    NotRecognized,
+    /// The mode is set
+    Set,
+    /// The mode is reset
+    Reset,
+    /// The mode is set and cannot be changed
+    PermanentlySet,
+    /// The mode is reset and cannot be changed
+    PermanentlyReset,
+}
+
 /// Control sequence introducer.
 pub const CSI: &str = "\x1b[";
+
+/// Operating system command.
+pub const OSC: &str = "\x1b]";
+
+/// String terminator.
+pub const ST: &str = "\x1b\\";
+
+/// Locks a mutex, recovering the data if another thread panicked with it held.
+pub(crate) fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
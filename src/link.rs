@@ -0,0 +1,318 @@
+use std::{
+    env,
+    fmt::{self, Write as _},
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{sys, Command, Link, LinkFallback, LinkTarget, Style, Term, OSC, ST};
+
+// Whether unsupported hyperlinks are printed with their URL after the text.
+static SHOW_URL: AtomicBool = AtomicBool::new(false);
+
+impl<'a> Link<'a> {
+    /// Constructs a link to `url`.
+    #[must_use]
+    pub const fn url(url: &'a str) -> Self {
+        Self { target: LinkTarget::Url(url), id: None }
+    }
+
+    /// Constructs a link to a local file or directory, which is linked with a
+    /// `file://` URI. Relative paths are resolved against the current
+    /// directory.
+    #[must_use]
+    pub const fn path(path: &'a Path) -> Self {
+        Self { target: LinkTarget::Path(path), id: None }
+    }
+
+    /// Sets the link ID, which lets the terminal treat separately printed
+    /// pieces of text with the same ID and target as a single link.
+    #[must_use]
+    pub const fn with_id(mut self, id: &'a str) -> Self {
+        self.id = Some(id);
+        self
+    }
+}
+
+impl<'a> Style<'a> {
+    /// Makes the text a hyperlink to `url`.
+    ///
+    /// When printed to output that does not support hyperlinks, the text is
+    /// printed as set by [`Term::set_link_fallback`].
+    ///
+    /// ```
+    /// use term_mods::Style;
+    ///
+    /// Style::this("the docs").link("https://docs.rs/term_mods").println();
+    /// // Prints "the docs" to stdout as a clickable link, with a newline.
+    /// ```
+    #[must_use]
+    pub const fn link(&mut self, url: &'a str) -> Self {
+        self.link = Some(Link::url(url));
+        *self
+    }
+
+    /// Makes the text a hyperlink to a local file or directory.
+    ///
+    /// Relative paths are resolved against the current directory.
+    #[must_use]
+    pub const fn link_path(&mut self, path: &'a Path) -> Self {
+        self.link = Some(Link::path(path));
+        *self
+    }
+
+    /// Sets the hyperlink ID, which lets the terminal treat separately printed
+    /// pieces of text with the same ID and target as a single link.
+    ///
+    /// Has no effect unless [`Style::link`] or [`Style::link_path`] was
+    /// called first.
+    #[must_use]
+    pub const fn link_id(&mut self, id: &'a str) -> Self {
+        if let Some(link) = &mut self.link {
+            link.id = Some(id);
+        }
+
+        *self
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+impl Term {
+    /// Writes `text` as a hyperlink to `url`, with an optional link ID.
+    ///
+    /// If the terminal is not expected to support hyperlinks, the text is
+    /// written as set by [`Term::set_link_fallback`] instead. Whether an
+    /// arbitrary writer is a terminal cannot be known, so like
+    /// [`Style::write_to`], it is treated as one.
+    ///
+    /// ```
+    /// use term_mods::Term;
+    ///
+    /// Term::hyperlink("the docs", "https://docs.rs/term_mods", None, &mut Term::stdout())?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn hyperlink<W: Write>(text: &str, url: &str, id: Option<&str>, w: &mut W) -> io::Result<()> {
+        let link = match id {
+            Some(id) => Link::url(url).with_id(id),
+            None => Link::url(url),
+        };
+
+        Command::Hyperlink { text, link }.write_to(w)
+    }
+
+    /// Returns true if stdout is a terminal that is expected to support
+    /// hyperlinks.
+    ///
+    /// Setting the `FORCE_HYPERLINK` environment variable to `1` or `0`
+    /// overrides the detection.
+    #[must_use]
+    pub fn supports_hyperlinks() -> bool {
+        links_supported(io::stdout().is_terminal())
+    }
+
+    /// Sets how hyperlinks in styled text are printed when the output does
+    /// not support them. Defaults to [`LinkFallback::Text`].
+    pub fn set_link_fallback(fallback: LinkFallback) {
+        SHOW_URL.store(fallback == LinkFallback::TextAndUrl, Ordering::Relaxed);
+    }
+}
+
+/// Returns true if output to a stream, which is a terminal if `is_terminal`,
+/// is expected to support hyperlinks.
+pub(crate) fn links_supported(is_terminal: bool) -> bool {
+    match env::var("FORCE_HYPERLINK").as_deref() {
+        Ok("1") => return true,
+        Ok("0") => return false,
+        _ => {},
+    }
+
+    if !is_terminal {
+        return false;
+    }
+
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    // Terminals known to print the sequence instead of ignoring it.
+    !matches!(term.as_str(), "" | "dumb" | "linux") && program != "Apple_Terminal"
+}
+
+/// Writes the text written by `write_text` as a hyperlink to `link`, or in
+/// the configured fallback form if `enabled` is false.
+pub(crate) fn write_link<W: fmt::Write>(
+    w: &mut W,
+    link: Link<'_>,
+    enabled: bool,
+    write_text: impl FnOnce(&mut W) -> fmt::Result
+) -> fmt::Result {
+    write_link_with(w, link, enabled, SHOW_URL.load(Ordering::Relaxed), write_text)
+}
+
+// Writes a hyperlink like `write_link`, with the URL after the text in the
+// fallback form if `show_url` is true.
+fn write_link_with<W: fmt::Write>(
+    w: &mut W,
+    link: Link<'_>,
+    enabled: bool,
+    show_url: bool,
+    write_text: impl FnOnce(&mut W) -> fmt::Result
+) -> fmt::Result {
+    // The URI is only built when it is written, as that allocates and may
+    // look up the host name.
+    if !enabled {
+        write_text(w)?;
+        return if show_url { write!(w, " ({})", uri(link.target)) } else { Ok(()) };
+    }
+
+    write!(w, "{OSC}8;")?;
+
+    if let Some(id) = link.id {
+        w.write_str("id=")?;
+
+        for c in id.chars().filter(|c| c.is_ascii_graphic() && !matches!(c, ':' | ';')) {
+            w.write_char(c)?;
+        }
+    }
+
+    write!(w, ";{}{ST}", uri(link.target))?;
+    write_text(w)?;
+    write!(w, "{OSC}8;;{ST}")
+}
+
+// Builds the URI of a link target.
+fn uri(target: LinkTarget<'_>) -> String {
+    match target {
+        LinkTarget::Url(url) => encode(url, |b| (0x21..=0x7e).contains(&b)),
+        LinkTarget::Path(path) => file_uri(path),
+    }
+}
+
+// Builds a `file://` URI, including the host name so that terminals on other
+// machines do not open the wrong file.
+fn file_uri(path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy();
+
+    // Windows paths use backslashes and start with a drive letter.
+    let path = if cfg!(windows) {
+        format!("/{}", path.replace('\\', "/"))
+    } else {
+        path.into_owned()
+    };
+
+    let host = sys::hostname().unwrap_or_default();
+    let path = encode(&path, |b| b.is_ascii_alphanumeric() || b"-._~/!$&'()*+,;=:@".contains(&b));
+
+    format!("file://{}{path}", encode(&host, |b| b.is_ascii_alphanumeric() || b"-.".contains(&b)))
+}
+
+// Percent-encodes every byte of `text` for which `keep` returns false.
+fn encode(text: &str, keep: impl Fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        if keep(byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fmt::Write as _, path::Path};
+
+    use super::{encode, links_supported, write_link_with};
+    use crate::{Link, Style, Term, OSC, ST};
+
+    // Formats `text` as a hyperlink, as written by `write_link`.
+    fn wrap(text: &str, link: Link<'_>, enabled: bool, show_url: bool) -> String {
+        let mut out = String::new();
+        write_link_with(&mut out, link, enabled, show_url, |w| w.write_str(text)).unwrap();
+        out
+    }
+
+    #[test]
+    fn wraps_url() {
+        assert_eq!(
+            wrap("docs", Link::url("https://example.com"), true, false),
+            format!("{OSC}8;;https://example.com{ST}docs{OSC}8;;{ST}")
+        );
+    }
+
+    #[test]
+    fn wraps_url_with_id() {
+        assert_eq!(
+            wrap("a", Link::url("x:y").with_id("i;d: 1"), true, false),
+            format!("{OSC}8;id=id1;x:y{ST}a{OSC}8;;{ST}")
+        );
+    }
+
+    #[test]
+    fn escapes_url() {
+        assert_eq!(
+            wrap("a", Link::url("https://e.com/a b\x1b"), true, false),
+            format!("{OSC}8;;https://e.com/a%20b%1B{ST}a{OSC}8;;{ST}")
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_url() {
+        assert_eq!(
+            wrap("a", Link::url("https://e.com/a%20b"), true, false),
+            format!("{OSC}8;;https://e.com/a%20b{ST}a{OSC}8;;{ST}")
+        );
+    }
+
+    #[test]
+    fn escapes_path() {
+        let uri = wrap("a", Link::path(Path::new("/tmp/a b#?%.txt")), true, false);
+
+        assert!(uri.starts_with(&format!("{OSC}8;;file://")));
+        assert!(uri.contains("/tmp/a%20b%23%3F%25.txt\x1b\\a"));
+    }
+
+    #[test]
+    fn style_link_keeps_id() {
+        let linked = Style::this("a").link("x:y").link_id("1").render(false, true);
+        assert_eq!(linked, format!("{OSC}8;id=1;x:y{ST}a{OSC}8;;{ST}"));
+
+        assert_eq!(Style::this("a").link_id("1").render(false, true), "a");
+    }
+
+    #[test]
+    fn encodes_unkept_bytes() {
+        assert_eq!(encode("é/", |b| b == b'/'), "%C3%A9/");
+    }
+
+    #[test]
+    fn falls_back_to_text() {
+        assert_eq!(wrap("docs", Link::url("https://example.com").with_id("1"), false, false), "docs");
+    }
+
+    #[test]
+    fn falls_back_to_text_and_url() {
+        assert_eq!(
+            wrap("docs", Link::url("https://example.com/a b"), false, true),
+            "docs (https://example.com/a%20b)"
+        );
+    }
+
+    #[test]
+    fn hyperlink_follows_support() {
+        let mut w = Vec::new();
+        Term::hyperlink("docs", "https://example.com", None, &mut w).unwrap();
+
+        let expected = if links_supported(true) {
+            format!("{OSC}8;;https://example.com{ST}docs{OSC}8;;{ST}")
+        } else {
+            "docs".to_string()
+        };
+
+        assert_eq!(String::from_utf8(w).unwrap(), expected);
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    env,
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{query, Background, Bg, Command, Fg, Rgb, Term, OSC, ST};
+
+impl Rgb {
+    /// Constructs a color from its red, green and blue components.
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses a color in the `rgb:r/g/b` form used by terminals, where each
+    /// component has one to four hex digits.
+    #[must_use]
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.strip_prefix("rgb:")?.split('/');
+        let mut next = || scale(parts.next()?);
+
+        let color = Self::new(next()?, next()?, next()?);
+        parts.next().is_none().then_some(color)
+    }
+
+    /// Returns the perceived brightness of the color, from 0.0 for black to
+    /// 1.0 for white.
+    #[must_use]
+    pub fn luminance(self) -> f64 {
+        (0.299 * f64::from(self.r) + 0.587 * f64::from(self.g) + 0.114 * f64::from(self.b)) / 255.0
+    }
+}
+
+impl From<Rgb> for Fg {
+    fn from(color: Rgb) -> Self {
+        Self::Rgb(color.r, color.g, color.b)
+    }
+}
+
+impl From<Rgb> for Bg {
+    fn from(color: Rgb) -> Self {
+        Self::Rgb(color.r, color.g, color.b)
+    }
+}
+
+impl std::fmt::Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rgb:{:02x}/{:02x}/{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+impl Term {
+    /// Sets the default foreground color.
+    pub fn set_fg_color<W: Write>(color: Rgb, w: &mut W) -> io::Result<()> {
+        Command::SetFgColor(color).write_to(w)
+    }
+
+    /// Sets the default background color.
+    pub fn set_bg_color<W: Write>(color: Rgb, w: &mut W) -> io::Result<()> {
+        Command::SetBgColor(color).write_to(w)
+    }
+
+    /// Sets the color of palette entry `index`.
+    pub fn set_palette_color<W: Write>(index: u8, color: Rgb, w: &mut W) -> io::Result<()> {
+        Command::SetPaletteColor(index, color).write_to(w)
+    }
+
+    /// Resets the default foreground color to the terminal's configured color.
+    pub fn reset_fg_color<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetFgColor.write_to(w)
+    }
+
+    /// Resets the default background color to the terminal's configured color.
+    pub fn reset_bg_color<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetBgColor.write_to(w)
+    }
+
+    /// Resets palette entry `index` to the terminal's configured color.
+    pub fn reset_palette_color<W: Write>(index: u8, w: &mut W) -> io::Result<()> {
+        Command::ResetPaletteColor(index).write_to(w)
+    }
+
+    /// Resets every palette entry to the terminal's configured colors.
+    pub fn reset_palette<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetPalette.write_to(w)
+    }
+
+    /// Asks the terminal for its default foreground color.
+    ///
+    /// Returns `None` if the terminal does not answer the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_fg_color(timeout: Duration) -> io::Result<Option<Rgb>> {
+        query_color("10;", timeout)
+    }
+
+    /// Asks the terminal for its default background color.
+    ///
+    /// Returns `None` if the terminal does not answer the query.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use term_mods::Term;
+    ///
+    /// if let Some(bg) = Term::query_bg_color(Duration::from_millis(200))? {
+    ///     println!("The background is {bg}");
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_bg_color(timeout: Duration) -> io::Result<Option<Rgb>> {
+        query_color("11;", timeout)
+    }
+
+    /// Asks the terminal for the color of palette entry `index`.
+    ///
+    /// Returns `None` if the terminal does not answer the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_palette_color(index: u8, timeout: Duration) -> io::Result<Option<Rgb>> {
+        query_color(&format!("4;{index};"), timeout)
+    }
+
+    /// Detects whether the terminal background is dark or light.
+    ///
+    /// The background color is queried with OSC 11, waiting up to `timeout`
+    /// for an answer. If the terminal does not answer, the `COLORFGBG`
+    /// environment variable and then the default themes of known terminals
+    /// are used instead.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use term_mods::{Background, Style, Term};
+    ///
+    /// let mut title = match Term::background(Duration::from_millis(100)) {
+    ///     Background::Light => Style::this("Title").blue(),
+    ///     Background::Dark | Background::Unknown => Style::this("Title").br_blue(),
+    /// };
+    ///
+    /// title.println();
+    /// ```
+    #[must_use]
+    pub fn background(timeout: Duration) -> Background {
+        match Self::query_bg_color(timeout) {
+            Ok(Some(color)) => background_of(color),
+            _ => background_from_env(),
+        }
+    }
+}
+
+// Classifies a background color.
+fn background_of(color: Rgb) -> Background {
+    if color.luminance() < 0.5 {
+        Background::Dark
+    } else {
+        Background::Light
+    }
+}
+
+// Guesses the background from the environment when the terminal cannot be asked.
+fn background_from_env() -> Background {
+    if let Ok(colors) = env::var("COLORFGBG") {
+        let background = background_from_colorfgbg(&colors);
+
+        if background != Background::Unknown {
+            return background;
+        }
+    }
+
+    let term = env::var("TERM").unwrap_or_default();
+
+    if term == "linux" || env::var_os("WT_SESSION").is_some() {
+        Background::Dark
+    } else if env::var("TERM_PROGRAM").is_ok_and(|p| p == "Apple_Terminal") {
+        Background::Light
+    } else {
+        Background::Unknown
+    }
+}
+
+// Parses `COLORFGBG`, which ends with the palette index of the background, as
+// in `15;0` or `15;default;0`.
+fn background_from_colorfgbg(colors: &str) -> Background {
+    match colors.rsplit(';').next().and_then(|bg| bg.parse::<u8>().ok()) {
+        Some(0..=6 | 8) => Background::Dark,
+        Some(7 | 9..=15) => Background::Light,
+        _ => Background::Unknown,
+    }
+}
+
+// Sends the color query `OSC prefix ?` and parses the reply, which repeats
+// the prefix before the color.
+fn query_color(prefix: &str, timeout: Duration) -> io::Result<Option<Rgb>> {
+    let request = format!("{OSC}{prefix}?{ST}{}", query::DA1);
+    let reply = query::query(&request, timeout, |reply| {
+        query::find_osc(reply, prefix.as_bytes()).is_some() || query::has_da1(reply)
+    })?;
+
+    Ok(parse_reply(&reply, prefix))
+}
+
+// Parses the color in the reply to a query with `prefix`.
+fn parse_reply(reply: &[u8], prefix: &str) -> Option<Rgb> {
+    let spec = query::find_osc(reply, prefix.as_bytes())?;
+    Rgb::parse(std::str::from_utf8(spec).ok()?)
+}
+
+// Scales a color component of one to four hex digits to eight bits.
+fn scale(hex: &str) -> Option<u8> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1 << (4 * hex.len())) - 1;
+
+    u8::try_from((value * 255 + max / 2) / max).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{background_from_colorfgbg, background_of, parse_reply};
+    use crate::{Background, Rgb};
+
+    test_modifier!(fg_set: set_fg_color(Rgb::new(255, 8, 160)) => "\x1b]10;rgb:ff/08/a0\x1b\\");
+    test_modifier!(bg_set: set_bg_color(Rgb::new(0, 0, 1)) => "\x1b]11;rgb:00/00/01\x1b\\");
+    test_modifier!(palette_set: set_palette_color(3, Rgb::new(1, 2, 3)) => "\x1b]4;3;rgb:01/02/03\x1b\\");
+    test_modifier!(fg_reset: reset_fg_color() => "\x1b]110\x1b\\");
+    test_modifier!(bg_reset: reset_bg_color() => "\x1b]111\x1b\\");
+    test_modifier!(palette_color_reset: reset_palette_color(3) => "\x1b]104;3\x1b\\");
+    test_modifier!(palette_reset: reset_palette() => "\x1b]104\x1b\\");
+
+    #[test]
+    fn parses_color_specs() {
+        assert_eq!(Rgb::parse("rgb:ffff/8080/0000"), Some(Rgb::new(255, 128, 0)));
+        assert_eq!(Rgb::parse("rgb:f/8/0"), Some(Rgb::new(255, 136, 0)));
+        assert_eq!(Rgb::parse("rgb:1e1e/1e/2"), Some(Rgb::new(30, 30, 34)));
+        assert_eq!(Rgb::parse("rgb:ff/ff"), None);
+        assert_eq!(Rgb::parse("rgb:ff/ff/ff/ff"), None);
+        assert_eq!(Rgb::parse("rgb:fffff/0/0"), None);
+        assert_eq!(Rgb::parse("#ffffff"), None);
+    }
+
+    #[test]
+    fn parses_color_replies() {
+        let reply = b"\x1b]4;1;rgb:cdcd/0000/0000\x1b\\\x1b[?62c";
+
+        assert_eq!(parse_reply(reply, "4;1;"), Some(Rgb::new(205, 0, 0)));
+        assert_eq!(parse_reply(reply, "4;12;"), None);
+        assert_eq!(parse_reply(b"\x1b]11;rgb:0000/0000/0000\x07", "11;"), Some(Rgb::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn classifies_backgrounds() {
+        assert_eq!(background_of(Rgb::new(0, 0, 0)), Background::Dark);
+        assert_eq!(background_of(Rgb::new(40, 42, 54)), Background::Dark);
+        assert_eq!(background_of(Rgb::new(253, 246, 227)), Background::Light);
+        assert_eq!(background_of(Rgb::new(255, 255, 255)), Background::Light);
+    }
+
+    #[test]
+    fn parses_colorfgbg() {
+        assert_eq!(background_from_colorfgbg("15;0"), Background::Dark);
+        assert_eq!(background_from_colorfgbg("0;default;15"), Background::Light);
+        assert_eq!(background_from_colorfgbg("7;8"), Background::Dark);
+        assert_eq!(background_from_colorfgbg("0;default"), Background::Unknown);
+        assert_eq!(background_from_colorfgbg(""), Background::Unknown);
+    }
+}
@@ -0,0 +1,143 @@
+//! Requests that the terminal answers by writing a reply to its input.
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::{input::Csi, sys, Term};
+
+/// Primary device attributes request, which every terminal answers. Sending
+/// it after another request detects terminals that ignore that request.
+pub const DA1: &str = "\x1b[c";
+
+/// Sends `request` to the terminal and collects its reply until `is_complete`
+/// returns true for the bytes received so far.
+///
+/// The terminal is put in raw mode while waiting so that the reply is neither
+/// line buffered nor echoed.
+pub fn query(
+    request: &str,
+    timeout: Duration,
+    is_complete: impl Fn(&[u8]) -> bool
+) -> io::Result<Vec<u8>> {
+    let _raw = Term::enable_raw_mode()?;
+
+    // Held back text must reach the terminal first, as the reply may depend
+    // on it, such as the cursor position.
+    Term::flush_output()?;
+    sys::write_tty(request.as_bytes())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut chunk = [0; 256];
+
+    while !is_complete(&reply) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "The terminal did not answer in time."
+            ));
+        }
+
+        if sys::poll_input(Some(remaining))? {
+            let len = sys::read_input(&mut chunk)?;
+
+            if len == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Terminal input has ended."
+                ));
+            }
+
+            reply.extend_from_slice(&chunk[..len]);
+        }
+    }
+
+    Ok(reply)
+}
+
+/// Returns the first result of `f` for the control sequences in `reply`.
+pub fn find_csi<T>(reply: &[u8], mut f: impl FnMut(&Csi<'_>) -> Option<T>) -> Option<T> {
+    (0..reply.len())
+        .filter(|&i| reply[i..].starts_with(b"\x1b["))
+        .filter_map(|i| Csi::split(&reply[i..]))
+        .find_map(|csi| f(&csi))
+}
+
+/// Asks for the state of DEC private `mode` with DECRQM.
+///
+/// Returns the reported state, or `None` if the terminal does not support
+/// DECRQM.
+pub fn decrqm(mode: u16, timeout: Duration) -> io::Result<Option<u16>> {
+    let reply = query(&format!("\x1b[?{mode}$p{DA1}"), timeout, has_da1)?;
+    Ok(find_csi(&reply, |csi| mode_state(csi, mode)))
+}
+
+// Returns the state in a DECRQM reply (`CSI ? mode ; state $ y`) for `mode`.
+fn mode_state(csi: &Csi<'_>, mode: u16) -> Option<u16> {
+    let is_reply = csi.private == Some(b'?') && csi.intermediates == b"$" && csi.final_byte == b'y';
+    (is_reply && csi.param(0) == Some(mode)).then(|| csi.param(1)).flatten()
+}
+
+/// Returns the body of the first complete operating system command in `reply`
+/// that starts with `prefix`, without the prefix and terminator.
+pub fn find_osc<'a>(reply: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    (0..reply.len())
+        .filter(|&i| reply[i..].starts_with(b"\x1b]") && reply[i + 2..].starts_with(prefix))
+        .find_map(|i| {
+            let body = &reply[i + 2 + prefix.len()..];
+            let end = body.iter().position(|b| matches!(b, b'\x07' | b'\x1b'))?;
+
+            (body[end] == b'\x07' || body.get(end + 1) == Some(&b'\\')).then(|| &body[..end])
+        })
+}
+
+/// Returns true if `reply` contains the answer to a [`DA1`] request.
+pub fn has_da1(reply: &[u8]) -> bool {
+    find_csi(reply, |csi| {
+        (csi.private == Some(b'?') && csi.final_byte == b'c').then_some(())
+    })
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_csi, find_osc, has_da1, mode_state};
+
+    #[test]
+    fn finds_reply_among_other_input() {
+        let reply = b"ab\x1b[A\x1b[12;300R";
+        let pos = find_csi(reply, |csi| {
+            (csi.final_byte == b'R').then(|| (csi.param(0), csi.param(1)))
+        });
+
+        assert_eq!(pos, Some((Some(12), Some(300))));
+    }
+
+    #[test]
+    fn finds_osc_reply() {
+        assert_eq!(find_osc(b"\x1b]10;rgb:0/0/0\x07", b"10;"), Some(&b"rgb:0/0/0"[..]));
+        assert_eq!(find_osc(b"\x1b]10;a\x1b\\\x1b]11;b\x1b\\", b"11;"), Some(&b"b"[..]));
+        assert_eq!(find_osc(b"\x1b]11;b\x1b", b"11;"), None);
+        assert_eq!(find_osc(b"\x1b[?62c", b"11;"), None);
+    }
+
+    #[test]
+    fn detects_da1_reply() {
+        assert!(has_da1(b"\x1b[?62;4;22c"));
+        assert!(has_da1(b"\x1b[?1u\x1b[?6c"));
+        assert!(!has_da1(b"\x1b[?1u"));
+        assert!(!has_da1(b"\x1b[?62;4"));
+    }
+
+    #[test]
+    fn finds_mode_state() {
+        let reply = b"\x1b[?1;2$y\x1b[?2026;2$y\x1b[?62c";
+
+        assert_eq!(find_csi(reply, |csi| mode_state(csi, 2026)), Some(2));
+        assert_eq!(find_csi(reply, |csi| mode_state(csi, 25)), None);
+    }
+}
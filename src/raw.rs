@@ -0,0 +1,120 @@
+use std::{
+    io,
+    panic,
+    sync::{Mutex, Once},
+};
+
+use crate::{
+    lock,
+    screen,
+    sys::{self, Termios, TtyMode},
+    Term,
+};
+
+// Settings of the controlling terminal before the crate first changed them.
+static ORIGINAL: Mutex<Option<Termios>> = Mutex::new(None);
+
+// Ensures the panic hook is only installed once.
+static PANIC_HOOK: Once = Once::new();
+
+/// Restores the terminal settings that were replaced when it was created.
+///
+/// Returned by [`Term::enable_raw_mode`] and [`Term::enable_cbreak_mode`].
+/// Guards may be nested, in which case each restores the settings that were
+/// active when it was created.
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::Term;
+///
+/// let _raw = Term::enable_raw_mode()?;
+/// // Keys are now delivered immediately and are not echoed.
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[must_use = "the previous terminal mode is restored when the guard is dropped"]
+pub struct RawModeGuard {
+    prev: Termios,
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = sys::set_termios(&self.prev);
+    }
+}
+
+impl Term {
+    /// Puts the controlling terminal into raw mode.
+    ///
+    /// Input is delivered byte by byte without echo, and keys such as `Ctrl+C`
+    /// no longer generate signals. Output processing is also disabled, so a
+    /// newline must be written as `"\r\n"` to return to the first column.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or its settings
+    /// cannot be changed.
+    pub fn enable_raw_mode() -> io::Result<RawModeGuard> {
+        set_mode(TtyMode::Raw)
+    }
+
+    /// Puts the controlling terminal into cbreak mode.
+    ///
+    /// Input is delivered key by key without echo, while signal keys and
+    /// output processing continue to work as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or its settings
+    /// cannot be changed.
+    pub fn enable_cbreak_mode() -> io::Result<RawModeGuard> {
+        set_mode(TtyMode::Cbreak)
+    }
+
+    /// Restores the controlling terminal to the settings it had before raw or
+    /// cbreak mode was first enabled.
+    ///
+    /// Does nothing if neither mode has been enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the settings cannot be applied.
+    pub fn disable_raw_mode() -> io::Result<()> {
+        let original = *lock(&ORIGINAL);
+
+        match original {
+            Some(termios) => sys::set_termios(&termios),
+            None => Ok(()),
+        }
+    }
+
+    /// Installs a panic hook that restores the terminal before the panic
+    /// message is printed, then runs the previously installed hook.
+    ///
+    /// The hook leaves the alternate screen entered by [`Term::alt_screen`] and
+    /// disables raw mode. This keeps a crash from leaving the user's shell
+    /// broken or hiding the panic message, even when the panic strategy is
+    /// `abort` and guards are never dropped. Calling this more than once has no
+    /// further effect.
+    pub fn install_panic_hook() {
+        PANIC_HOOK.call_once(|| {
+            let prev_hook = panic::take_hook();
+
+            panic::set_hook(Box::new(move |info| {
+                let _ = screen::leave_alt_screen();
+                let _ = Term::disable_raw_mode();
+                prev_hook(info);
+            }));
+        });
+    }
+}
+
+// Switches to `mode`, remembering the original settings the first time.
+fn set_mode(mode: TtyMode) -> io::Result<RawModeGuard> {
+    let prev = sys::get_termios()?;
+
+    lock(&ORIGINAL).get_or_insert(prev);
+    sys::set_termios(&sys::with_mode(&prev, mode))?;
+
+    Ok(RawModeGuard { prev })
+}
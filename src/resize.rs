@@ -0,0 +1,167 @@
+use std::{
+    io,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{lock, Term, WinSize};
+
+// Senders for every live resize subscription.
+static SUBSCRIBERS: Mutex<Vec<Sender<WinSize>>> = Mutex::new(Vec::new());
+
+// Whether the `SIGWINCH` handler and its watcher thread are running.
+static WATCHING: Mutex<bool> = Mutex::new(false);
+
+/// A subscription to terminal resize notifications.
+///
+/// Each resize of the terminal window delivers the new [`WinSize`]. Events can
+/// be awaited in a blocking loop, either with [`ResizeEvents::recv`] or by
+/// iterating, or checked between input polls with [`ResizeEvents::try_recv`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::Term;
+///
+/// for size in Term::on_resize()? {
+///     println!("Resized to {} rows and {} columns", size.rows, size.cols);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ResizeEvents {
+    rx: Receiver<WinSize>,
+}
+
+impl ResizeEvents {
+    /// Blocks until the terminal is resized and returns its new size.
+    ///
+    /// Returns `None` if resize notifications are no longer being delivered.
+    #[must_use]
+    pub fn recv(&self) -> Option<WinSize> {
+        self.rx.recv().ok()
+    }
+
+    /// Returns the new size if the terminal has been resized, without blocking.
+    ///
+    /// Only the most recent size is returned if several resizes are pending.
+    #[must_use]
+    pub fn try_recv(&self) -> Option<WinSize> {
+        let mut latest = None;
+
+        loop {
+            match self.rx.try_recv() {
+                Ok(size) => latest = Some(size),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return latest,
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the terminal to be resized.
+    #[must_use]
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<WinSize> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for ResizeEvents {
+    type Item = WinSize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Term {
+    /// Subscribes to terminal resize notifications.
+    ///
+    /// The first subscription installs a `SIGWINCH` handler, which chains to
+    /// any previously installed handler, and starts a background thread that
+    /// queries the new size with [`Term::get_win_size`] after each resize.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal handler cannot be installed or the
+    /// platform does not support resize notifications.
+    pub fn on_resize() -> io::Result<ResizeEvents> {
+        let (tx, rx) = mpsc::channel();
+
+        start_watching()?;
+        lock(&SUBSCRIBERS).push(tx);
+
+        Ok(ResizeEvents { rx })
+    }
+}
+
+// Sends the new size to every subscriber, dropping those that have gone away.
+fn notify(size: WinSize) {
+    lock(&SUBSCRIBERS).retain(|tx| tx.send(size).is_ok());
+}
+
+#[cfg(unix)]
+fn start_watching() -> io::Result<()> {
+    use std::io::Read;
+
+    let mut watching = lock(&WATCHING);
+
+    if *watching {
+        return Ok(());
+    }
+
+    let (mut pipe, write) = crate::sys::pipe()?;
+
+    // The thread is started before the handler is installed, so that a
+    // failure leaves nothing behind to clean up on a later attempt. If the
+    // handler cannot be installed, the write end is closed and the thread
+    // ends.
+    std::thread::Builder::new()
+        .name("term_mods-resize".into())
+        .spawn(move || {
+            let mut buf = [0; 64];
+
+            // Each read may coalesce several signals into one notification.
+            while let Ok(n) = pipe.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+
+                if let Ok(size) = Term::get_win_size() {
+                    notify(size);
+                }
+            }
+        })?;
+
+    crate::sys::forward_winch(write)?;
+
+    *watching = true;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn start_watching() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Resize notifications are not supported on this platform."
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Term, WinSize};
+
+    #[test]
+    fn notify_reaches_subscribers() {
+        let events = Term::on_resize().unwrap();
+        let size = WinSize { rows: 51, cols: 317, width_px: None, height_px: None };
+
+        super::notify(size);
+        super::notify(WinSize { cols: 318, ..size });
+
+        assert_eq!(events.try_recv().map(|s| s.cols), Some(318));
+        assert_eq!(events.recv_timeout(Duration::from_millis(1)), None);
+    }
+}
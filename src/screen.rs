@@ -0,0 +1,193 @@
+use std::{
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{CSI, Term};
+
+// Whether an `AltScreenGuard` currently has the alternate screen active.
+static ALT_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the terminal on the alternate screen buffer until dropped.
+///
+/// Created by [`Term::alt_screen`]. When dropped, text attributes are reset,
+/// the cursor is shown and the main screen is restored along with the
+/// user's scrollback and cursor position. This also happens before a panic
+/// message is printed, as [`Term::alt_screen`] installs the panic hook from
+/// [`Term::install_panic_hook`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::Term;
+///
+/// let screen = Term::alt_screen()?;
+/// // Draw a full-screen interface...
+/// drop(screen);
+/// // The user's scrollback is back as it was.
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[must_use = "the main screen is restored when the guard is dropped"]
+pub struct AltScreenGuard {
+    // Whether this guard entered the alternate screen, rather than one that
+    // was already active.
+    owned: bool,
+}
+
+impl Drop for AltScreenGuard {
+    fn drop(&mut self) {
+        if self.owned {
+            let _ = leave_alt_screen();
+        }
+    }
+}
+
+/// Holds back drawing until dropped, so that a frame written through it is
+/// drawn all at once without flicker.
+///
+/// Created by [`Term::sync_update`]. Writes go to the wrapped writer, and
+/// when dropped the synchronized update is ended and the writer flushed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::Term;
+///
+/// let mut stdout = std::io::stdout().lock();
+/// let mut frame = Term::sync_update(&mut stdout)?;
+/// Term::clr_scr(&mut frame)?;
+/// Term::write(1, 1, "Redrawn without flicker", &mut frame)?;
+/// drop(frame);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[must_use = "the update ends when the guard is dropped"]
+pub struct SyncUpdateGuard<'a, W: Write> {
+    w: &'a mut W,
+}
+
+impl<W: Write> Write for SyncUpdateGuard<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.w.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+impl<W: Write> Drop for SyncUpdateGuard<'_, W> {
+    fn drop(&mut self) {
+        let _ = Term::end_sync_update(self.w).and_then(|()| self.w.flush());
+    }
+}
+
+impl Term {
+    /// Switches stdout to the alternate screen buffer and returns a guard that
+    /// switches back when dropped.
+    ///
+    /// The first call installs the panic hook from [`Term::install_panic_hook`],
+    /// so that a panic message is printed on the main screen rather than lost
+    /// with the alternate one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to stdout fails.
+    pub fn alt_screen() -> io::Result<AltScreenGuard> {
+        Self::install_panic_hook();
+
+        if ALT_SCREEN.swap(true, Ordering::SeqCst) {
+            return Ok(AltScreenGuard { owned: false });
+        }
+
+        // Written after any printed text held back by the flush policy.
+        let mut stdout = Self::stdout();
+
+        if let Err(e) = Self::enter_alt_screen(&mut stdout).and_then(|()| stdout.flush()) {
+            ALT_SCREEN.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+
+        Ok(AltScreenGuard { owned: true })
+    }
+
+    /// Begins a synchronized update on `w` and returns a guard that ends it
+    /// when dropped.
+    ///
+    /// Terminals without synchronized output draw the frame as it arrives;
+    /// use [`Term::supports_sync_update`] to check for support.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `w` fails.
+    pub fn sync_update<W: Write>(w: &mut W) -> io::Result<SyncUpdateGuard<'_, W>> {
+        Self::begin_sync_update(w)?;
+        Ok(SyncUpdateGuard { w })
+    }
+}
+
+/// Leaves the alternate screen if a guard has it active, resetting text
+/// attributes and showing the cursor first.
+pub(crate) fn leave_alt_screen() -> io::Result<()> {
+    if !ALT_SCREEN.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    // Printed text held back by the flush policy belongs on the alternate screen.
+    let _ = Term::flush_output();
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(format!("{CSI}0m").as_bytes())?;
+    Term::show_cursor(&mut stdout)?;
+    Term::leave_alt_screen(&mut stdout)?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use crate::Term;
+
+    // Records what is written to it and whether it has been flushed since.
+    #[derive(Default)]
+    struct Recorder {
+        buf: Vec<u8>,
+        flushed: bool,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.flushed = false;
+            self.buf.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sync_update_wraps_frame() {
+        let mut w = Vec::new();
+
+        let mut frame = Term::sync_update(&mut w).unwrap();
+        frame.write_all(b"frame").unwrap();
+        drop(frame);
+
+        assert_eq!(w, b"\x1b[?2026hframe\x1b[?2026l");
+    }
+
+    #[test]
+    fn sync_update_flushes_on_drop() {
+        let mut w = Recorder::default();
+
+        let mut frame = Term::sync_update(&mut w).unwrap();
+        frame.write_all(b"frame").unwrap();
+        assert!(!frame.w.flushed);
+        drop(frame);
+
+        assert!(w.buf.ends_with(b"\x1b[?2026l"));
+        assert!(w.flushed);
+    }
+}
@@ -0,0 +1,487 @@
+//! Thin wrappers around the platform APIs used by the rest of the crate.
+
+use std::io;
+
+use crate::WinSize;
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        ffi::c_void,
+        fs::File,
+        io,
+        os::unix::io::{AsRawFd, FromRawFd, RawFd},
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            OnceLock,
+        },
+        time::Duration,
+    };
+
+    use crate::WinSize;
+
+    // Queries the window size of the terminal referred to by `fd`.
+    pub fn win_size(fd: RawFd) -> io::Result<WinSize> {
+        // SAFETY: `winsize` is a plain C struct for which all-zeroes is valid.
+        let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+
+        // SAFETY: `TIOCGWINSZ` only writes into the `winsize` we pass it.
+        if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if ws.ws_row == 0 || ws.ws_col == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The terminal reported a size of zero."
+            ));
+        }
+
+        Ok(WinSize {
+            rows: ws.ws_row,
+            cols: ws.ws_col,
+            width_px: (ws.ws_xpixel != 0).then_some(ws.ws_xpixel),
+            height_px: (ws.ws_ypixel != 0).then_some(ws.ws_ypixel),
+        })
+    }
+
+    // Queries the window size using stdout, stderr, stdin and then the
+    // controlling terminal, returning the first successful result.
+    pub fn tty_win_size() -> io::Result<WinSize> {
+        let mut last_err = None;
+
+        for fd in [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO] {
+            match win_size(fd) {
+                Ok(size) => return Ok(size),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        match File::open("/dev/tty") {
+            Ok(tty) => win_size(tty.as_raw_fd()),
+            Err(e) => Err(last_err.unwrap_or(e)),
+        }
+    }
+
+    // The controlling terminal, opened on first use.
+    static TTY: OnceLock<Option<File>> = OnceLock::new();
+
+    pub fn tty_fd() -> RawFd {
+        let tty = TTY.get_or_init(|| {
+            File::options().read(true).write(true).open("/dev/tty").ok()
+        });
+
+        tty.as_ref().map_or(libc::STDIN_FILENO, AsRawFd::as_raw_fd)
+    }
+
+    // The descriptor that input is read from: stdin if it is a terminal, and
+    // the controlling terminal otherwise.
+    pub fn input_fd() -> RawFd {
+        // SAFETY: `isatty` has no preconditions.
+        if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+            libc::STDIN_FILENO
+        } else {
+            tty_fd()
+        }
+    }
+
+    pub fn poll_readable(fd: RawFd, timeout: Option<Duration>) -> io::Result<bool> {
+        let timeout_ms = timeout.map_or(-1, |t| {
+            // Round up so that short, non-zero timeouts still wait.
+            let ms = t.as_nanos().div_ceil(1_000_000);
+            libc::c_int::try_from(ms).unwrap_or(libc::c_int::MAX)
+        });
+
+        let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+
+        // SAFETY: `pollfd` is a single, valid entry.
+        match unsafe { libc::poll(&raw mut pollfd, 1, timeout_ms) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+
+                // A signal such as `SIGWINCH` interrupts the wait early.
+                if err.kind() == io::ErrorKind::Interrupted {
+                    Ok(false)
+                } else {
+                    Err(err)
+                }
+            },
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    pub fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+            let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+
+            if let Ok(n) = usize::try_from(n) {
+                return Ok(n);
+            }
+
+            let err = io::Error::last_os_error();
+
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    pub fn write_all(fd: RawFd, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            // SAFETY: `buf` is valid for reads of `buf.len()` bytes.
+            let n = unsafe { libc::write(fd, buf.as_ptr().cast(), buf.len()) };
+
+            match usize::try_from(n) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => buf = &buf[n..],
+                Err(_) => {
+                    let err = io::Error::last_os_error();
+
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_termios(fd: RawFd) -> io::Result<libc::termios> {
+        // SAFETY: `termios` is a plain C struct for which all-zeroes is valid.
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+
+        // SAFETY: `tcgetattr` only writes into the struct we pass it.
+        if unsafe { libc::tcgetattr(fd, &raw mut termios) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(termios)
+    }
+
+    pub fn set_termios(fd: RawFd, termios: &libc::termios) -> io::Result<()> {
+        // SAFETY: `tcsetattr` only reads from the struct we pass it.
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, termios) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    // Write end of the self-pipe used to forward `SIGWINCH` out of the
+    // signal handler.
+    static WINCH_FD: AtomicI32 = AtomicI32::new(-1);
+
+    // The `SIGWINCH` disposition that was replaced, so that it can still be
+    // invoked. Set once, before the handler is first installed.
+    static PREV_WINCH: OnceLock<libc::sigaction> = OnceLock::new();
+
+    type InfoHandler = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut c_void);
+
+    extern "C" fn on_winch(sig: libc::c_int, info: *mut libc::siginfo_t, context: *mut c_void) {
+        let fd = WINCH_FD.load(Ordering::Relaxed);
+
+        if fd >= 0 {
+            // SAFETY: `write` is async-signal-safe. A full pipe just means a
+            // notification is already pending, so the result is ignored.
+            unsafe { libc::write(fd, b"W".as_ptr().cast(), 1) };
+        }
+
+        let Some(prev) = PREV_WINCH.get() else {
+            return;
+        };
+
+        let handler = prev.sa_sigaction;
+
+        if handler == libc::SIG_DFL || handler == libc::SIG_IGN {
+            return;
+        }
+
+        if prev.sa_flags & libc::SA_SIGINFO == 0 {
+            // SAFETY: A non-`SA_SIGINFO` handler has this signature.
+            let f: extern "C" fn(libc::c_int) = unsafe { std::mem::transmute(handler) };
+            f(sig);
+        } else {
+            // SAFETY: An `SA_SIGINFO` handler has this signature, and is
+            // passed the arguments this handler received.
+            let f: InfoHandler = unsafe { std::mem::transmute(handler) };
+            f(sig, info, context);
+        }
+    }
+
+    // Creates a pipe whose ends are close-on-exec, returning (read, write).
+    pub fn pipe() -> io::Result<(File, File)> {
+        let mut fds = [0; 2];
+
+        // SAFETY: `pipe` writes exactly two file descriptors into `fds`.
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: Both descriptors were just created and are owned here.
+        let (read, write) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+        for fd in fds {
+            // SAFETY: `fd` is a valid, open descriptor.
+            unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+        }
+
+        // The signal handler must never block on a full pipe.
+        // SAFETY: `fds[1]` is a valid, open descriptor.
+        unsafe {
+            let flags = libc::fcntl(fds[1], libc::F_GETFL);
+            libc::fcntl(fds[1], libc::F_SETFL, flags | libc::O_NONBLOCK);
+        }
+
+        Ok((read, write))
+    }
+
+    // Installs the `SIGWINCH` handler, which writes a byte to `write` for
+    // each delivered signal. Must not be called again once it succeeds. If it
+    // fails, `write` is closed.
+    pub fn forward_winch(write: File) -> io::Result<()> {
+        // SAFETY: `sigaction` is a plain C struct for which all-zeroes is valid.
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+
+        action.sa_sigaction = on_winch as InfoHandler as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+
+        // Record the replaced disposition before `on_winch` can run. Until the
+        // handler is installed, it is still the current one.
+        PREV_WINCH.get_or_init(|| {
+            // SAFETY: `sigaction` is a plain C struct for which all-zeroes is
+            // valid, and passing no new action only reads the current one.
+            unsafe {
+                let mut prev: libc::sigaction = std::mem::zeroed();
+                libc::sigaction(libc::SIGWINCH, std::ptr::null(), &raw mut prev);
+                prev
+            }
+        });
+
+        WINCH_FD.store(write.as_raw_fd(), Ordering::Relaxed);
+
+        // SAFETY: `action` is fully initialized here.
+        let installed = unsafe {
+            libc::sigemptyset(&raw mut action.sa_mask);
+            libc::sigaction(libc::SIGWINCH, &raw const action, std::ptr::null_mut()) == 0
+        };
+
+        if !installed {
+            let err = io::Error::last_os_error();
+            WINCH_FD.store(-1, Ordering::Relaxed);
+            return Err(err);
+        }
+
+        // The write end lives for the rest of the process.
+        std::mem::forget(write);
+        Ok(())
+    }
+}
+
+/// Queries the terminal window size directly from the operating system.
+#[cfg(unix)]
+pub fn tty_win_size() -> io::Result<WinSize> {
+    unix::tty_win_size()
+}
+
+/// Saved terminal settings for the controlling terminal.
+#[cfg(unix)]
+#[derive(Clone, Copy)]
+pub struct Termios(libc::termios);
+
+/// Saved terminal settings for the controlling terminal.
+#[cfg(not(unix))]
+#[derive(Clone, Copy)]
+pub struct Termios;
+
+/// Line discipline modes that the controlling terminal can be switched to.
+#[derive(Clone, Copy)]
+pub enum TtyMode {
+    /// No line buffering, echo, signals or output processing.
+    Raw,
+    /// No line buffering or echo, but signals and output processing remain.
+    Cbreak,
+}
+
+/// Returns the name of this machine, if it can be determined.
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|b| *b == 0)?;
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Returns the name of this machine, if it can be determined.
+#[cfg(not(unix))]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+/// Writes all of `buf` to the controlling terminal.
+#[cfg(unix)]
+pub fn write_tty(buf: &[u8]) -> io::Result<()> {
+    unix::write_all(unix::tty_fd(), buf)
+}
+
+/// Returns the current settings of the controlling terminal.
+#[cfg(unix)]
+pub fn get_termios() -> io::Result<Termios> {
+    unix::get_termios(unix::tty_fd()).map(Termios)
+}
+
+/// Applies `termios` to the controlling terminal.
+#[cfg(unix)]
+pub fn set_termios(termios: &Termios) -> io::Result<()> {
+    unix::set_termios(unix::tty_fd(), &termios.0)
+}
+
+/// Returns `termios` modified for the given mode.
+#[cfg(unix)]
+#[must_use]
+pub fn with_mode(termios: &Termios, mode: TtyMode) -> Termios {
+    let mut t = termios.0;
+
+    match mode {
+        // SAFETY: `cfmakeraw` only modifies the struct it is given.
+        TtyMode::Raw => unsafe { libc::cfmakeraw(&raw mut t) },
+        TtyMode::Cbreak => t.c_lflag &= !(libc::ICANON | libc::ECHO),
+    }
+
+    t.c_cc[libc::VMIN] = 1;
+    t.c_cc[libc::VTIME] = 0;
+    Termios(t)
+}
+
+/// Waits up to `timeout`, or indefinitely if `None`, for terminal input to
+/// become available. Returns `false` on timeout or if a signal arrives first.
+#[cfg(unix)]
+pub fn poll_input(timeout: Option<std::time::Duration>) -> io::Result<bool> {
+    unix::poll_readable(unix::input_fd(), timeout)
+}
+
+/// Reads available terminal input into `buf`.
+#[cfg(unix)]
+pub fn read_input(buf: &mut [u8]) -> io::Result<usize> {
+    unix::read(unix::input_fd(), buf)
+}
+
+/// Writes all of `buf` to the controlling terminal.
+#[cfg(not(unix))]
+pub fn write_tty(_buf: &[u8]) -> io::Result<()> {
+    Err(unsupported())
+}
+
+/// Returns the current settings of the controlling terminal.
+#[cfg(not(unix))]
+pub fn get_termios() -> io::Result<Termios> {
+    Err(unsupported())
+}
+
+/// Applies `termios` to the controlling terminal.
+#[cfg(not(unix))]
+pub fn set_termios(_termios: &Termios) -> io::Result<()> {
+    Err(unsupported())
+}
+
+/// Returns `termios` modified for the given mode.
+#[cfg(not(unix))]
+#[must_use]
+pub fn with_mode(termios: &Termios, _mode: TtyMode) -> Termios {
+    *termios
+}
+
+/// Waits up to `timeout`, or indefinitely if `None`, for terminal input to
+/// become available. Returns `false` on timeout or if a signal arrives first.
+#[cfg(not(unix))]
+pub fn poll_input(_timeout: Option<std::time::Duration>) -> io::Result<bool> {
+    Err(unsupported())
+}
+
+/// Reads available terminal input into `buf`.
+#[cfg(not(unix))]
+pub fn read_input(_buf: &mut [u8]) -> io::Result<usize> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Terminal input and modes are not supported on this platform."
+    )
+}
+
+/// Creates a pipe whose ends are close-on-exec, returning (read, write).
+#[cfg(unix)]
+pub fn pipe() -> io::Result<(std::fs::File, std::fs::File)> {
+    unix::pipe()
+}
+
+/// Installs a `SIGWINCH` handler that writes a byte to `write` whenever the
+/// terminal is resized. Must not be called again once it succeeds. If it
+/// fails, `write` is closed.
+#[cfg(unix)]
+pub fn forward_winch(write: std::fs::File) -> io::Result<()> {
+    unix::forward_winch(write)
+}
+
+/// Queries the terminal window size directly from the operating system.
+#[cfg(not(unix))]
+pub fn tty_win_size() -> io::Result<WinSize> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Querying the terminal size is not supported on this platform."
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{with_mode, Termios, TtyMode};
+
+    // Settings with line buffering, echo, signals and output processing on.
+    fn cooked() -> Termios {
+        // SAFETY: All-zero bytes are a valid `termios`.
+        let mut t: libc::termios = unsafe { std::mem::zeroed() };
+
+        t.c_lflag = libc::ICANON | libc::ECHO | libc::ISIG;
+        t.c_oflag = libc::OPOST;
+        t.c_cc[libc::VMIN] = 0;
+        t.c_cc[libc::VTIME] = 5;
+        Termios(t)
+    }
+
+    #[test]
+    fn raw_clears_all_processing() {
+        let Termios(t) = with_mode(&cooked(), TtyMode::Raw);
+
+        assert_eq!(t.c_lflag & (libc::ICANON | libc::ECHO | libc::ISIG), 0);
+        assert_eq!(t.c_oflag & libc::OPOST, 0);
+    }
+
+    #[test]
+    fn cbreak_keeps_signals_and_output_processing() {
+        let Termios(t) = with_mode(&cooked(), TtyMode::Cbreak);
+
+        assert_eq!(t.c_lflag & (libc::ICANON | libc::ECHO), 0);
+        assert_eq!(t.c_lflag & libc::ISIG, libc::ISIG);
+        assert_eq!(t.c_oflag & libc::OPOST, libc::OPOST);
+    }
+
+    #[test]
+    fn reads_wait_for_one_byte() {
+        for mode in [TtyMode::Raw, TtyMode::Cbreak] {
+            let Termios(t) = with_mode(&cooked(), mode);
+
+            assert_eq!(t.c_cc[libc::VMIN], 1);
+            assert_eq!(t.c_cc[libc::VTIME], 0);
+        }
+    }
+}
@@ -1,194 +1,670 @@
 use std::{
+    env,
+    ffi::OsStr,
     io::{self, Write},
-    process::{Command, Stdio},
+    process::{self, Stdio},
     str::FromStr,
+    time::Duration,
 };
 
-use crate::{CSI, Term};
+use crate::{
+    query, sys, Command, CursorShape, KittyFlags, ModeState, MouseEncoding, MouseMode, CSI, Term,
+    WinSize,
+};
 
 #[allow(clippy::missing_errors_doc)]
 impl Term {
     /// Scrolls the terminal screen up `num` lines.
-    pub fn scroll_u<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}S").as_bytes())
+    pub fn scroll_u<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::ScrollUp(num).write_to(w)
     }
 
     /// Scrolls the terminal screen down `num` lines.
-    pub fn scroll_d<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}T").as_bytes())
+    pub fn scroll_d<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::ScrollDown(num).write_to(w)
+    }
+
+    /// Restricts scrolling to the rows from `top` to `bottom`, inclusive.
+    ///
+    /// Scrolling, line insertion and line deletion then only affect that
+    /// region, leaving rows outside of it, such as a header or footer, in
+    /// place. The cursor moves to the top left of the screen.
+    pub fn set_scroll_region<W: Write>(top: u16, bottom: u16, w: &mut W) -> io::Result<()> {
+        Command::SetScrollRegion(top, bottom).write_to(w)
+    }
+
+    /// Resets the scroll region to the full screen.
+    pub fn reset_scroll_region<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetScrollRegion.write_to(w)
+    }
+
+    /// Enables left and right margins, which can then be set with
+    /// [`Term::set_lr_margins`].
+    ///
+    /// While enabled, `CSI s` sets the margins instead of saving the cursor,
+    /// so use [`Term::save_cursor`] rather than that sequence.
+    pub fn enable_lr_margins<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::EnableLrMargins.write_to(w)
+    }
+
+    /// Disables left and right margins.
+    pub fn disable_lr_margins<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::DisableLrMargins.write_to(w)
+    }
+
+    /// Restricts scrolling and editing to the columns from `left` to `right`,
+    /// inclusive. Left and right margins must first be enabled with
+    /// [`Term::enable_lr_margins`].
+    pub fn set_lr_margins<W: Write>(left: u16, right: u16, w: &mut W) -> io::Result<()> {
+        Command::SetLrMargins(left, right).write_to(w)
+    }
+
+    /// Resets the left and right margins to the full screen width.
+    ///
+    /// This is the same `CSI s` sequence that saves the cursor position while
+    /// left and right margins are disabled, so it should only be written after
+    /// [`Term::enable_lr_margins`]. Margins can instead be dropped altogether
+    /// with [`Term::disable_lr_margins`].
+    pub fn reset_lr_margins<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetLrMargins.write_to(w)
+    }
+
+    /// Inserts `num` blank lines at the cursor, pushing the lines below it
+    /// down within the scroll region.
+    pub fn insert_ln<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::InsertLines(num).write_to(w)
+    }
+
+    /// Deletes `num` lines at the cursor, pulling the lines below it up within
+    /// the scroll region.
+    pub fn delete_ln<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::DeleteLines(num).write_to(w)
+    }
+
+    /// Inserts `num` blank cells at the cursor, shifting the rest of the line
+    /// right.
+    pub fn insert_ch<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::InsertChars(num).write_to(w)
+    }
+
+    /// Deletes `num` cells at the cursor, shifting the rest of the line left.
+    pub fn delete_ch<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::DeleteChars(num).write_to(w)
+    }
+
+    /// Blanks `num` cells starting at the cursor without shifting the line.
+    pub fn erase_ch<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::EraseChars(num).write_to(w)
+    }
+
+    /// Moves the cursor up one line, scrolling the scroll region down if the
+    /// cursor is on its top row.
+    pub fn reverse_index<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ReverseIndex.write_to(w)
     }
 
     /// Clears the full terminal screen.
     pub fn clr_scr<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}2J").as_bytes())
+        Command::ClearScreen.write_to(w)
     }
 
     /// Clears the terminal screen from the cursor to the beginning of the screen.
     pub fn clr_scr_to_start<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}1J").as_bytes())
+        Command::ClearScreenToStart.write_to(w)
     }
 
     /// Clears the terminal screen from the cursor to the end of the screen.
     pub fn clr_scr_to_end<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}0J").as_bytes())
+        Command::ClearScreenToEnd.write_to(w)
     }
 
     /// Clears the current line.
     pub fn clr_ln<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}2K").as_bytes())
+        Command::ClearLine.write_to(w)
     }
 
     /// Clears the current line from the cursor to the beginning of the line.
     pub fn clr_ln_to_start<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}1K").as_bytes())
+        Command::ClearLineToStart.write_to(w)
     }
 
     /// Clears the current line from the cursor to the end of the line.
     pub fn clr_ln_to_end<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}0K").as_bytes())
+        Command::ClearLineToEnd.write_to(w)
     }
 
     /// Shows the terminal cursor.
     pub fn show_cursor<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}?25h").as_bytes())
+        Command::ShowCursor.write_to(w)
     }
 
     /// Hides the terminal cursor.
     pub fn hide_cursor<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}?25l").as_bytes())
+        Command::HideCursor.write_to(w)
+    }
+
+    /// Switches to the alternate screen buffer, saving the cursor position.
+    ///
+    /// See [`Term::alt_screen`] for a guard that switches back automatically.
+    pub fn enter_alt_screen<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::EnterAltScreen.write_to(w)
+    }
+
+    /// Switches back to the main screen buffer, restoring the cursor position.
+    pub fn leave_alt_screen<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::LeaveAltScreen.write_to(w)
+    }
+
+    /// Begins a synchronized update, during which the terminal holds back
+    /// drawing until [`Term::end_sync_update`] so that a redraw appears at once.
+    ///
+    /// See [`Term::sync_update`] for a guard that ends the update
+    /// automatically. Terminals that do not support synchronized output
+    /// ignore this.
+    pub fn begin_sync_update<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::BeginSyncUpdate.write_to(w)
+    }
+
+    /// Ends a synchronized update, drawing everything written since it began.
+    pub fn end_sync_update<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::EndSyncUpdate.write_to(w)
+    }
+
+    /// Asks the terminal whether it supports synchronized output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn supports_sync_update(timeout: Duration) -> io::Result<bool> {
+        let state = Self::query_mode(2026, timeout)?;
+        Ok(state.is_supported() && state != ModeState::PermanentlyReset)
+    }
+
+    /// Sets the shape of the cursor and whether it blinks.
+    pub fn set_cursor_shape<W: Write>(shape: CursorShape, w: &mut W) -> io::Result<()> {
+        Command::SetCursorShape(shape).write_to(w)
+    }
+
+    /// Sets the cursor color using an RGB value.
+    pub fn set_cursor_color<W: Write>(r: u8, g: u8, b: u8, w: &mut W) -> io::Result<()> {
+        Command::SetCursorColor(r, g, b).write_to(w)
+    }
+
+    /// Resets the cursor color to the terminal's default.
+    pub fn reset_cursor_color<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::ResetCursorColor.write_to(w)
+    }
+
+    /// Sets the window (or tab) title.
+    ///
+    /// Control characters are removed from `title` so that it cannot end the
+    /// sequence early or inject other escape sequences.
+    pub fn set_title<W: Write>(title: &str, w: &mut W) -> io::Result<()> {
+        Command::SetTitle(title).write_to(w)
+    }
+
+    /// Sets the icon name, which some terminals show for minimized windows or
+    /// in their tab bar.
+    ///
+    /// Control characters are removed from `name`.
+    pub fn set_icon_name<W: Write>(name: &str, w: &mut W) -> io::Result<()> {
+        Command::SetIconName(name).write_to(w)
+    }
+
+    /// Sets both the window title and the icon name.
+    ///
+    /// Control characters are removed from `title`.
+    pub fn set_title_and_icon<W: Write>(title: &str, w: &mut W) -> io::Result<()> {
+        Command::SetTitleAndIcon(title).write_to(w)
+    }
+
+    /// Saves the current window title and icon name on the terminal's title
+    /// stack, so they can be restored with [`Term::pop_title`].
+    pub fn push_title<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::PushTitle.write_to(w)
+    }
+
+    /// Restores the window title and icon name most recently saved with
+    /// [`Term::push_title`].
+    pub fn pop_title<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::PopTitle.write_to(w)
+    }
+
+    /// Saves the cursor position, along with the text attributes in effect.
+    pub fn save_cursor<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::SaveCursor.write_to(w)
+    }
+
+    /// Restores the cursor position and text attributes saved by
+    /// [`Term::save_cursor`].
+    pub fn restore_cursor<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::RestoreCursor.write_to(w)
+    }
+
+    /// Asks the terminal where the cursor is.
+    ///
+    /// Returns the 1-based (row, column), matching [`Term::cursor_goto`]. Any
+    /// other input that arrives while waiting for the reply is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn cursor_pos(timeout: Duration) -> io::Result<(u16, u16)> {
+        let find_pos = |reply: &[u8]| query::find_csi(reply, |csi| {
+            if csi.private.is_none() && csi.final_byte == b'R' {
+                Some((csi.param(0)?, csi.param(1)?))
+            } else {
+                None
+            }
+        });
+
+        let reply = query::query(&format!("{CSI}6n"), timeout, |r| find_pos(r).is_some())?;
+
+        find_pos(&reply).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid cursor position report."
+        ))
     }
 
     /// Moves the cursor `num` cells up.
-    pub fn cursor_u<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}A").as_bytes())
+    pub fn cursor_u<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveUp(num).write_to(w)
     }
 
     /// Moves the cursor `num` cells down.
-    pub fn cursor_d<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}B").as_bytes())
+    pub fn cursor_d<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveDown(num).write_to(w)
     }
 
     /// Moves the cursor `num` cells right.
-    pub fn cursor_r<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}C").as_bytes())
+    pub fn cursor_r<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveRight(num).write_to(w)
     }
 
     /// Moves the cursor `num` cells left.
-    pub fn cursor_l<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}D").as_bytes())
+    pub fn cursor_l<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveLeft(num).write_to(w)
     }
 
     /// Moves the cursor to column `num`.
-    pub fn cursor_col<W: Write>(num: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{num}G").as_bytes())
+    pub fn cursor_col<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveToColumn(num).write_to(w)
     }
 
     /// Moves the cursor to row `row` and column `col`.
-    pub fn cursor_goto<W: Write>(row: u8, col: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{row};{col}H").as_bytes())
+    pub fn cursor_goto<W: Write>(row: u16, col: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveTo(row, col).write_to(w)
     }
 
     /// Moves the cursor to the bottom left position on the screen.
-    pub fn cursor_bl<W: Write>(rows: u8, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{rows};1H").as_bytes())
+    pub fn cursor_bl<W: Write>(rows: u16, w: &mut W) -> io::Result<()> {
+        Command::MoveToBottomLeft(rows).write_to(w)
     }
 
     /// Moves the cursor to the top left position on the screen.
     pub fn cursor_tl<W: Write>(w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}1;1H").as_bytes())
+        Command::MoveToTopLeft.write_to(w)
     }
 
-    /// Writes a message that is centered on the screen.
-    pub fn write_centered<W: Write>(row: u8, width: u8, msg: &str, w: &mut W) -> io::Result<()> {
-        let Ok(len) = u8::try_from(msg.len()) else {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Unable to convert the message length to u8."
-            ));
-        };
-
-        if len > width {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Message cannot be longer than the screen width."
-            ));
-        }
+    /// Enables mouse reporting using the given tracking mode and encoding.
+    ///
+    /// Reports are decoded by [`EventReader`](crate::input::EventReader) as
+    /// [`Event::Mouse`](crate::input::Event::Mouse) events.
+    pub fn enable_mouse<W: Write>(
+        mode: MouseMode,
+        encoding: MouseEncoding,
+        w: &mut W
+    ) -> io::Result<()> {
+        Command::EnableMouse(mode, encoding).write_to(w)
+    }
 
-        let col = (width / 2) - (len / 2);
-        w.write_all(format!("{CSI}{row};{col}H{msg}").as_bytes())
+    /// Disables all mouse reporting modes and encodings.
+    pub fn disable_mouse<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::DisableMouse.write_to(w)
+    }
+
+    /// Enables bracketed paste mode.
+    ///
+    /// Pasted text is then reported by [`EventReader`](crate::input::EventReader)
+    /// as a single [`Event::Paste`](crate::input::Event::Paste) instead of as
+    /// individual key presses.
+    pub fn enable_bracketed_paste<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::EnableBracketedPaste.write_to(w)
+    }
+
+    /// Disables bracketed paste mode.
+    pub fn disable_bracketed_paste<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::DisableBracketedPaste.write_to(w)
+    }
+
+    /// Enables focus reporting.
+    ///
+    /// The terminal then reports when its window gains or loses focus, which
+    /// [`EventReader`](crate::input::EventReader) decodes as
+    /// [`Event::FocusGained`](crate::input::Event::FocusGained) and
+    /// [`Event::FocusLost`](crate::input::Event::FocusLost).
+    pub fn enable_focus_reporting<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::EnableFocusReporting.write_to(w)
+    }
+
+    /// Disables focus reporting.
+    pub fn disable_focus_reporting<W: Write>(w: &mut W) -> io::Result<()> {
+        Command::DisableFocusReporting.write_to(w)
+    }
+
+    /// Pushes kitty keyboard protocol enhancement flags onto the terminal's
+    /// stack, replacing the flags currently in effect.
+    ///
+    /// Terminals that do not support the protocol ignore this and keep using
+    /// the legacy encoding, which [`EventReader`](crate::input::EventReader)
+    /// continues to decode.
+    pub fn push_kitty_flags<W: Write>(flags: KittyFlags, w: &mut W) -> io::Result<()> {
+        Command::PushKittyFlags(flags).write_to(w)
+    }
+
+    /// Pops `num` entries from the terminal's kitty keyboard protocol stack,
+    /// restoring the flags that were in effect before they were pushed.
+    pub fn pop_kitty_flags<W: Write>(num: u16, w: &mut W) -> io::Result<()> {
+        Command::PopKittyFlags(num).write_to(w)
+    }
+
+    /// Asks the terminal which kitty keyboard protocol flags are in effect.
+    ///
+    /// Returns `None` if the terminal does not support the protocol. Any other
+    /// input that arrives while waiting for the reply is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no controlling terminal or it does not
+    /// answer within `timeout`.
+    pub fn query_kitty_flags(timeout: Duration) -> io::Result<Option<KittyFlags>> {
+        let request = format!("{CSI}?u{}", query::DA1);
+        let reply = query::query(&request, timeout, query::has_da1)?;
+
+        Ok(query::find_csi(&reply, |csi| {
+            if csi.private == Some(b'?') && csi.final_byte == b'u' {
+                csi.param(0).map(|bits| KittyFlags::from_bits(u8::try_from(bits).unwrap_or(0)))
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Writes a message that is centered on the screen.
+    pub fn write_centered<W: Write>(row: u16, width: u16, msg: &str, w: &mut W) -> io::Result<()> {
+        Command::WriteCentered(row, width, msg).write_to(w)
     }
 
     /// Writes a message to a given position on the screen.
-    pub fn write<W: Write>(row: u8, col: u8, msg: &str, w: &mut W) -> io::Result<()> {
-        w.write_all(format!("{CSI}{row};{col}H{msg}").as_bytes())
+    pub fn write<W: Write>(row: u16, col: u16, msg: &str, w: &mut W) -> io::Result<()> {
+        Command::WriteAt(row, col, msg).write_to(w)
     }
 
-    /// Attempts to get the terminal size using `tput` and returns a sensible default
-    /// of (20, 80) if there is an error.
+    /// Gets the terminal window size.
+    ///
+    /// The size is queried with `TIOCGWINSZ` on stdout, stderr, stdin and then
+    /// `/dev/tty`, falling back to the `COLUMNS` and `LINES` environment
+    /// variables and finally to `tput`. Pixel dimensions are only available
+    /// when the terminal reports them.
+    pub fn get_win_size() -> io::Result<WinSize> {
+        sys::tty_win_size()
+            .or_else(|_| size_from_env())
+            .or_else(|_| size_from_tput())
+    }
+
+    /// Gets the terminal size.
     ///
     /// Returns (height, width).
-    #[must_use]
-    pub fn get_term_size() -> (u8, u8) {
-        // A child process executed with the `output` method does not inherit
-        // the parent process' stdin by default. Therefore, we must ensure that
-        // stdin is inherited from the parent process in order for tput to query
-        // the correct terminal for its size.
-        let Ok(tput_out) = Command::new("tput")
-            .args(["cols", "lines"])
-            .stdin(Stdio::inherit())
-            .output() else { return (20, 80) };
+    pub fn get_term_size() -> io::Result<(u16, u16)> {
+        Self::get_win_size().map(|size| (size.rows, size.cols))
+    }
+}
+
+impl std::fmt::Display for CursorShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Default => write!(f, "0"),
+            Self::BlinkingBlock => write!(f, "1"),
+            Self::SteadyBlock => write!(f, "2"),
+            Self::BlinkingUnderline => write!(f, "3"),
+            Self::SteadyUnderline => write!(f, "4"),
+            Self::BlinkingBar => write!(f, "5"),
+            Self::SteadyBar => write!(f, "6"),
+        }
+    }
+}
+
+impl std::fmt::Display for MouseMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::X10 => write!(f, "9"),
+            Self::Normal => write!(f, "1000"),
+            Self::ButtonEvent => write!(f, "1002"),
+            Self::AnyEvent => write!(f, "1003"),
+        }
+    }
+}
+
+impl std::fmt::Display for MouseEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Sgr => write!(f, "1006"),
+            Self::SgrPixels => write!(f, "1016"),
+        }
+    }
+}
 
-        let mut size_iter = tput_out.stdout.split(|byte| *byte == b'\n');
+// Gets the terminal size from the `COLUMNS` and `LINES` environment variables.
+fn size_from_env() -> io::Result<WinSize> {
+    let cols = env::var_os("COLUMNS");
+    let lines = env::var_os("LINES");
 
-        // Parse the width and height from the bytes slice.
-        let Some(width_bytes)  = size_iter.next() else { return (20, 80) };
-        let Some(height_bytes) = size_iter.next() else { return (20, 80) };
+    size_from_vars(cols.as_deref(), lines.as_deref())
+}
 
-        let Ok(width_str)  = std::str::from_utf8(width_bytes)  else { return (20, 80) };
-        let Ok(height_str) = std::str::from_utf8(height_bytes) else { return (20, 80) };
+// Builds a terminal size from the values of `COLUMNS` and `LINES`.
+fn size_from_vars(cols: Option<&OsStr>, lines: Option<&OsStr>) -> io::Result<WinSize> {
+    let cols = parse_dimension(cols.map(OsStr::as_encoded_bytes))?;
+    let rows = parse_dimension(lines.map(OsStr::as_encoded_bytes))?;
 
-        let Ok(width)  = u8::from_str(width_str)  else { return (20, 80) };
-        let Ok(height) = u8::from_str(height_str) else { return (20, 80) };
+    Ok(WinSize { rows, cols, width_px: None, height_px: None })
+}
 
-        (height, width)
+// Gets the terminal size using `tput`.
+fn size_from_tput() -> io::Result<WinSize> {
+    // A child process executed with the `output` method does not inherit
+    // the parent process' stdin by default. Therefore, we must ensure that
+    // stdin is inherited from the parent process in order for tput to query
+    // the correct terminal for its size.
+    let tput_out = process::Command::new("tput")
+        .args(["cols", "lines"])
+        .stdin(Stdio::inherit())
+        .output()?;
+
+    if !tput_out.status.success() {
+        return Err(io::Error::other(
+            "Unable to query the terminal size with tput."
+        ));
     }
+
+    let mut size_iter = tput_out.stdout.split(|byte| *byte == b'\n');
+
+    // Parse the width and height from the bytes slice.
+    let cols = parse_dimension(size_iter.next())?;
+    let rows = parse_dimension(size_iter.next())?;
+
+    Ok(WinSize { rows, cols, width_px: None, height_px: None })
 }
 
-//#[cfg(test)]
-//mod tests {
-//    use crate::Term;
+// Parses a single, non-zero terminal dimension.
+fn parse_dimension(bytes: Option<&[u8]>) -> io::Result<u16> {
+    let Some(bytes) = bytes else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Missing terminal dimension."
+        ));
+    };
+
+    let Ok(dim_str) = std::str::from_utf8(bytes) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Terminal dimension is not valid UTF-8."
+        ));
+    };
+
+    match u16::from_str(dim_str.trim()) {
+        Ok(dim) if dim > 0 => Ok(dim),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Terminal dimension {dim_str:?} is not in the range 1..=65535.")
+        )),
+    }
+}
 
-    //macro_rules! test_modifier {
-    //    ($label:ident: $modifier:expr => $ansi:expr) => {
-    //        #[test]
-    //        fn $label() {
-    //            assert_eq!($modifier, $ansi.to_string());
-    //        }
-    //    };
-    //}
+#[cfg(test)]
+mod tests {
+    use crate::{CursorShape, KittyFlags, MouseEncoding, MouseMode, Term};
 
     // Terminal scrolling tests
-    //test_modifier!(scroll_up: Term::scroll_u(1, &mut w) => "\x1b[1S");
-    //test_modifier!(scroll_down: Term::scroll_d(4, &mut w) => "\x1b[4T");
+    test_modifier!(scroll_up: scroll_u(1) => "\x1b[1S");
+    test_modifier!(scroll_down: scroll_d(4) => "\x1b[4T");
+
+    // Scroll region and margin tests
+    test_modifier!(scroll_region: set_scroll_region(2, 299) => "\x1b[2;299r");
+    test_modifier!(scroll_region_reset: reset_scroll_region() => "\x1b[r");
+    test_modifier!(lr_margins_on: enable_lr_margins() => "\x1b[?69h");
+    test_modifier!(lr_margins_off: disable_lr_margins() => "\x1b[?69l");
+    test_modifier!(lr_margins: set_lr_margins(5, 300) => "\x1b[5;300s");
+    test_modifier!(lr_margins_reset: reset_lr_margins() => "\x1b[s");
+    test_modifier!(reverse_index: reverse_index() => "\x1bM");
+
+    #[test]
+    fn invalid_regions() {
+        let mut w = Vec::new();
+        assert!(Term::set_scroll_region(0, 10, &mut w).is_err());
+        assert!(Term::set_scroll_region(10, 10, &mut w).is_err());
+        assert!(Term::set_scroll_region(11, 10, &mut w).is_err());
+        assert!(Term::set_lr_margins(0, 10, &mut w).is_err());
+        assert!(Term::set_lr_margins(20, 10, &mut w).is_err());
+        assert!(w.is_empty());
+    }
+
+    // Line and character editing tests
+    test_modifier!(insert_lines: insert_ln(3) => "\x1b[3L");
+    test_modifier!(delete_lines: delete_ln(2) => "\x1b[2M");
+    test_modifier!(insert_chars: insert_ch(4) => "\x1b[4@");
+    test_modifier!(delete_chars: delete_ch(5) => "\x1b[5P");
+    test_modifier!(erase_chars: erase_ch(260) => "\x1b[260X");
 
     // Clear screen tests
-    //test_modifier!(clr_scr_all: Term::clr_scr(&mut w) => "\x1b[2J");
-    //test_modifier!(clr_scr_to_end: Term::clr_scr_to_end(&mut w) => "\x1b[0J");
-    //test_modifier!(clr_scr_to_start: Term::clr_scr_to_start(&mut w) => "\x1b[1J");
+    test_modifier!(clr_scr_all: clr_scr() => "\x1b[2J");
+    test_modifier!(clr_scr_to_end: clr_scr_to_end() => "\x1b[0J");
+    test_modifier!(clr_scr_to_start: clr_scr_to_start() => "\x1b[1J");
 
     // Clear line tests
-    //test_modifier!(clr_line_all: Term::clr_ln(&mut w) => "\x1b[2K");
-    //test_modifier!(clr_line_to_end: Term::clr_ln_to_end(&mut w) => "\x1b[0K");
-    //test_modifier!(clr_line_to_start: Term::clr_ln_to_start(&mut w) => "\x1b[1K");
+    test_modifier!(clr_line_all: clr_ln() => "\x1b[2K");
+    test_modifier!(clr_line_to_end: clr_ln_to_end() => "\x1b[0K");
+    test_modifier!(clr_line_to_start: clr_ln_to_start() => "\x1b[1K");
+
+    // Alternate screen tests
+    test_modifier!(alt_screen_enter: enter_alt_screen() => "\x1b[?1049h");
+    test_modifier!(alt_screen_leave: leave_alt_screen() => "\x1b[?1049l");
+
+    // Synchronized output tests
+    test_modifier!(sync_update_begin: begin_sync_update() => "\x1b[?2026h");
+    test_modifier!(sync_update_end: end_sync_update() => "\x1b[?2026l");
+
+    // Window title tests
+    test_modifier!(title: set_title("Build 42%") => "\x1b]2;Build 42%\x1b\\");
+    test_modifier!(icon_name: set_icon_name("build") => "\x1b]1;build\x1b\\");
+    test_modifier!(title_and_icon: set_title_and_icon("job") => "\x1b]0;job\x1b\\");
+    test_modifier!(title_push: push_title() => "\x1b[22;0t");
+    test_modifier!(title_pop: pop_title() => "\x1b[23;0t");
+    test_modifier!(
+        title_sanitized:
+        set_title("a\x1b\\\x07b\nc\u{9c}d\x7f") => "\x1b]2;a\\bcd\x1b\\"
+    );
 
     // Cursor modifier tests
-    //test_modifier!(cursor_show: Term::show_cursor(&mut w) => "\x1b[?25h");
-    //test_modifier!(cursor_hide: Term::hide_cursor(&mut w) => "\x1b[?25l");
-    //test_modifier!(cursor_up: Term::cursor_u(3, &mut w) => "\x1b[3A");
-    //test_modifier!(cursor_down: Term::cursor_d(3, &mut w) => "\x1b[3B");
-    //test_modifier!(cursor_forward: Term::cursor_r(3, &mut w) => "\x1b[3C");
-    //test_modifier!(cursor_back: Term::cursor_l(3, &mut w) => "\x1b[3D");
-    //test_modifier!(cursor_col: Term::cursor_col(3, &mut w) => "\x1b[3G");
-    //test_modifier!(cursor_goto: Term::cursor_goto(13, 12, &mut w) => "\x1b[13;12H");
-    //test_modifier!(cursor_tl: Term::cursor_tl(&mut w) => "\x1b[1;1H");
-//}
+    test_modifier!(cursor_save: save_cursor() => "\x1b7");
+    test_modifier!(cursor_default: set_cursor_shape(CursorShape::Default) => "\x1b[0 q");
+    test_modifier!(cursor_block: set_cursor_shape(CursorShape::SteadyBlock) => "\x1b[2 q");
+    test_modifier!(cursor_underline: set_cursor_shape(CursorShape::BlinkingUnderline) => "\x1b[3 q");
+    test_modifier!(cursor_bar: set_cursor_shape(CursorShape::SteadyBar) => "\x1b[6 q");
+    test_modifier!(cursor_color: set_cursor_color(255, 8, 160) => "\x1b]12;rgb:ff/08/a0\x1b\\");
+    test_modifier!(cursor_color_reset: reset_cursor_color() => "\x1b]112\x1b\\");
+    test_modifier!(cursor_restore: restore_cursor() => "\x1b8");
+    test_modifier!(cursor_show: show_cursor() => "\x1b[?25h");
+    test_modifier!(cursor_hide: hide_cursor() => "\x1b[?25l");
+    test_modifier!(cursor_up: cursor_u(3) => "\x1b[3A");
+    test_modifier!(cursor_down: cursor_d(3) => "\x1b[3B");
+    test_modifier!(cursor_forward: cursor_r(3) => "\x1b[3C");
+    test_modifier!(cursor_back: cursor_l(3) => "\x1b[3D");
+    test_modifier!(cursor_col: cursor_col(300) => "\x1b[300G");
+    test_modifier!(cursor_goto: cursor_goto(13, 312) => "\x1b[13;312H");
+    test_modifier!(cursor_bl: cursor_bl(90) => "\x1b[90;1H");
+    test_modifier!(cursor_tl: cursor_tl() => "\x1b[1;1H");
+
+    // Mouse reporting tests
+    test_modifier!(
+        mouse_normal_sgr:
+        enable_mouse(MouseMode::Normal, MouseEncoding::Sgr) => "\x1b[?1000h\x1b[?1006h"
+    );
+    test_modifier!(
+        mouse_any_pixels:
+        enable_mouse(MouseMode::AnyEvent, MouseEncoding::SgrPixels) => "\x1b[?1003h\x1b[?1016h"
+    );
+    test_modifier!(
+        mouse_off:
+        disable_mouse() => "\x1b[?1016l\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l\x1b[?9l"
+    );
+
+    // Bracketed paste tests
+    test_modifier!(paste_on: enable_bracketed_paste() => "\x1b[?2004h");
+    test_modifier!(paste_off: disable_bracketed_paste() => "\x1b[?2004l");
+
+    // Focus reporting tests
+    test_modifier!(focus_on: enable_focus_reporting() => "\x1b[?1004h");
+    test_modifier!(focus_off: disable_focus_reporting() => "\x1b[?1004l");
+
+    // Kitty keyboard protocol tests
+    test_modifier!(
+        kitty_push:
+        push_kitty_flags(KittyFlags::DISAMBIGUATE | KittyFlags::REPORT_EVENTS) => "\x1b[>3u"
+    );
+    test_modifier!(kitty_pop: pop_kitty_flags(1) => "\x1b[<1u");
+
+    // Writing tests
+    test_modifier!(write_msg: write(5, 280, "hi") => "\x1b[5;280Hhi");
+    test_modifier!(write_centered_wide: write_centered(2, 320, "abcd") => "\x1b[2;158Habcd");
+
+    #[test]
+    fn write_centered_too_long() {
+        let mut w = Vec::new();
+        assert!(Term::write_centered(1, 3, "abcd", &mut w).is_err());
+        assert!(w.is_empty());
+    }
+
+    #[test]
+    fn parse_dimension_wide() {
+        assert_eq!(super::parse_dimension(Some(b"316")).unwrap(), 316);
+        assert!(super::parse_dimension(Some(b"70000")).is_err());
+        assert!(super::parse_dimension(Some(b"")).is_err());
+        assert!(super::parse_dimension(Some(b"0")).is_err());
+        assert!(super::parse_dimension(None).is_err());
+    }
+
+    #[test]
+    fn size_from_vars() {
+        use std::ffi::OsStr;
+
+        let size = super::size_from_vars(Some(OsStr::new("320")), Some(OsStr::new("90"))).unwrap();
+        assert_eq!((size.rows, size.cols), (90, 320));
+        assert_eq!((size.width_px, size.height_px), (None, None));
+
+        assert!(super::size_from_vars(Some(OsStr::new("320")), None).is_err());
+        assert!(super::size_from_vars(None, Some(OsStr::new("90"))).is_err());
+    }
+}
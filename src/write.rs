@@ -1,4 +1,104 @@
-use crate::{Bg, Fg, Style};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fmt,
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use crate::{lock, Bg, ColorPolicy, Fg, FlushPolicy, Style, Term};
+
+// The color policy set with `Term::set_color_policy`.
+static COLOR_POLICY: AtomicU8 = AtomicU8::new(0);
+
+// The variables `ColorPolicy::Auto` depends on, which are read once since
+// reading them allocates.
+static AUTO_ENV: OnceLock<[Option<OsString>; 3]> = OnceLock::new();
+
+// Whether stdout and stderr are terminals, checked once rather than on
+// every print.
+static STDOUT_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+static STDERR_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+// The flush policy set with `Term::set_flush_policy`.
+static FLUSH_POLICY: AtomicU8 = AtomicU8::new(0);
+
+// Printed output that the flush policy has held back.
+static STDOUT_PENDING: Pending = Pending::new();
+static STDERR_PENDING: Pending = Pending::new();
+
+// Pending output is written once it reaches this size, whatever the policy.
+const MAX_PENDING: usize = 64 * 1024;
+
+// Output held back for a stream.
+struct Pending {
+    buf: Mutex<Vec<u8>>,
+    // Whether `buf` holds any output, so that it can be checked cheaply
+    // before every terminal command.
+    held: AtomicBool,
+}
+
+impl Pending {
+    const fn new() -> Self {
+        Self {
+            buf: Mutex::new(Vec::new()),
+            held: AtomicBool::new(false),
+        }
+    }
+}
+
+/// A handle to stdout that shares the buffer of text printed with
+/// [`Style::print`], created by [`Term::stdout`].
+///
+/// Writes through the handle are held back and written following the flush
+/// policy, just like printed text, so terminal commands keep their place
+/// among it. Flushing the handle writes everything held for stdout.
+///
+/// # Examples
+///
+/// ```no_run
+/// use term_mods::{FlushPolicy, Style, Term};
+///
+/// Term::set_flush_policy(FlushPolicy::Manual);
+/// let mut stdout = Term::stdout();
+///
+/// for row in 1..=10 {
+///     Term::cursor_goto(row, 1, &mut stdout)?;
+///     Style::this("#").green().print()?;
+/// }
+///
+/// // The cursor moves and the text are written together, in order.
+/// Term::flush_output()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct SharedStdout {
+    _private: (),
+}
+
+impl Write for SharedStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        emit(&STDOUT_PENDING, &mut io::stdout().lock(), |pending| {
+            pending.extend_from_slice(buf);
+            Ok(())
+        })?;
+
+        Ok(buf.len())
+    }
+
+    // Formats each write in one go, so that a command is held or written as
+    // a whole rather than piece by piece.
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> io::Result<()> {
+        emit(&STDOUT_PENDING, &mut io::stdout().lock(), |pending| pending.write_fmt(args))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        flush_pending(&STDOUT_PENDING, &mut io::stdout().lock())
+    }
+}
 
 // Describes the various printing methods.
 enum WriteKind {
@@ -9,61 +109,289 @@ enum WriteKind {
 }
 
 #[allow(clippy::missing_errors_doc)]
-impl<'a> Style<'a> {
+impl Style<'_> {
     /// Prints the styled string to stdout.
-    pub fn print(&mut self) -> std::io::Result<()> {
+    pub fn print(&mut self) -> io::Result<()> {
         self.write_common(&WriteKind::Stdout)
     }
 
     /// Prints the styled string to stderr.
-    pub fn eprint(&mut self) -> std::io::Result<()> {
+    pub fn eprint(&mut self) -> io::Result<()> {
         self.write_common(&WriteKind::Stderr)
     }
 
     /// Prints the styled string to stdout with a newline.
-    pub fn println(&mut self) -> std::io::Result<()> {
+    pub fn println(&mut self) -> io::Result<()> {
         self.write_common(&WriteKind::StdoutNewline)
     }
 
     /// Prints the styled string to stderr with a newline.
-    pub fn eprintln(&mut self) -> std::io::Result<()> {
+    pub fn eprintln(&mut self) -> io::Result<()> {
         self.write_common(&WriteKind::StderrNewline)
     }
 
+    /// Writes the styled string to `w`, which may be a locked stdout handle,
+    /// a file, a socket or an in-memory buffer.
+    ///
+    /// Whether an arbitrary writer is a terminal cannot be known, so with
+    /// [`ColorPolicy::Auto`] it is styled as if it were one. Use
+    /// [`Style::write_to_stream`] for writers that can be checked.
+    ///
+    /// ```
+    /// use term_mods::{ColorPolicy, Style, Term};
+    ///
+    /// Term::set_color_policy(ColorPolicy::Always);
+    ///
+    /// let mut out = Vec::new();
+    /// Style::this("Saved").green().write_to(&mut out)?;
+    ///
+    /// assert_eq!(out, b"\x1b[32mSaved\x1b[0m");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_to<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self.styled_for(true))
+    }
+
+    /// Writes the styled string to `w`, styling it only if the color policy
+    /// allows it for `w`, which is checked for being a terminal.
+    pub fn write_to_stream<W: Write + IsTerminal>(&mut self, w: &mut W) -> io::Result<()> {
+        let is_terminal = w.is_terminal();
+        write!(w, "{}", self.styled_for(is_terminal))
+    }
+
+    /// Writes the styled string to a [`fmt::Write`] target, such as a
+    /// `String` or a [`fmt::Formatter`], following the color policy like
+    /// [`Style::write_to`].
+    pub fn write_to_fmt<W: fmt::Write>(&mut self, w: &mut W) -> fmt::Result {
+        write!(w, "{}", self.styled_for(true))
+    }
+
     // Common logic for printing to stdout and stderr.
-    fn write_common(&mut self, kind: &WriteKind) -> std::io::Result<()> {
-        use std::io::Write;
+    fn write_common(&mut self, kind: &WriteKind) -> io::Result<()> {
+        if self.text.is_empty() {
+            return Ok(());
+        }
+
+        let is_stdout = matches!(*kind, WriteKind::Stdout | WriteKind::StdoutNewline);
 
-        let mut ansi_string = match self.text.len() {
-            0 => return Ok(()),
-            _ => self.get_ansi(),
+        let is_terminal = if is_stdout {
+            *STDOUT_IS_TERMINAL.get_or_init(|| io::stdout().is_terminal())
+        } else {
+            *STDERR_IS_TERMINAL.get_or_init(|| io::stderr().is_terminal())
         };
 
-        match *kind {
-            WriteKind::Stdout => {
-                std::io::stdout().write_all(ansi_string.as_bytes())?;
-                std::io::stdout().flush()?;
-            },
-            WriteKind::Stderr => {
-                std::io::stderr().write_all(ansi_string.as_bytes())?;
-                std::io::stderr().flush()?;
-            },
-            WriteKind::StdoutNewline => {
-                ansi_string.push('\n');
-                std::io::stdout().write_all(ansi_string.as_bytes())?;
-                std::io::stdout().flush()?;
-            },
-            WriteKind::StderrNewline => {
-                ansi_string.push('\n');
-                std::io::stderr().write_all(ansi_string.as_bytes())?;
-                std::io::stderr().flush()?;
+        let styled = self.styled_for(is_terminal);
+        let newline = matches!(*kind, WriteKind::StdoutNewline | WriteKind::StderrNewline);
+
+        let add = |pending: &mut Vec<u8>| {
+            write!(pending, "{styled}")?;
+
+            if newline {
+                pending.push(b'\n');
+            }
+
+            Ok(())
+        };
+
+        if is_stdout {
+            emit(&STDOUT_PENDING, &mut io::stdout().lock(), add)
+        } else {
+            emit(&STDERR_PENDING, &mut io::stderr().lock(), add)
+        }
+    }
+}
+
+impl ColorPolicy {
+    /// Returns true if output to a stream, which is a terminal if
+    /// `is_terminal`, should be styled.
+    #[must_use]
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                let [no_color, force, term] = AUTO_ENV.get_or_init(|| {
+                    ["NO_COLOR", "CLICOLOR_FORCE", "TERM"].map(env::var_os)
+                });
+
+                auto_enabled(is_terminal, no_color.as_deref(), force.as_deref(), term.as_deref())
             },
         }
+    }
+}
+
+impl Term {
+    /// Sets when styled text is printed with its colors and attributes.
+    /// Defaults to [`ColorPolicy::Auto`].
+    pub fn set_color_policy(policy: ColorPolicy) {
+        let code = match policy {
+            ColorPolicy::Auto => 0,
+            ColorPolicy::Always => 1,
+            ColorPolicy::Never => 2,
+        };
+
+        COLOR_POLICY.store(code, Ordering::Relaxed);
+    }
+
+    /// Returns the color policy set with [`Term::set_color_policy`].
+    #[must_use]
+    pub fn color_policy() -> ColorPolicy {
+        match COLOR_POLICY.load(Ordering::Relaxed) {
+            1 => ColorPolicy::Always,
+            2 => ColorPolicy::Never,
+            _ => ColorPolicy::Auto,
+        }
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+impl Term {
+    /// Sets when text printed with [`Style::print`] and its variants is
+    /// flushed. Defaults to [`FlushPolicy::PerCall`].
+    ///
+    /// Text held back by the policy is not seen by other writers to stdout,
+    /// such as `io::stdout()` or Rust's own `print!` macros. Write terminal
+    /// commands through [`Term::stdout`] so that they are held along with the
+    /// text, and call [`Term::flush_output`] before using other writers,
+    /// reading input or exiting. Held text is lost if the program exits
+    /// without it being flushed.
+    ///
+    /// ```no_run
+    /// use term_mods::{FlushPolicy, Style, Term};
+    ///
+    /// Term::set_flush_policy(FlushPolicy::Manual);
+    ///
+    /// for _ in 0..1000 {
+    ///     Style::this("#").green().print()?;
+    /// }
+    ///
+    /// // Everything is written at once.
+    /// Term::flush_output()?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_flush_policy(policy: FlushPolicy) {
+        let code = match policy {
+            FlushPolicy::PerCall => 0,
+            FlushPolicy::Newline => 1,
+            FlushPolicy::Manual => 2,
+        };
+
+        FLUSH_POLICY.store(code, Ordering::Relaxed);
+    }
+
+    /// Returns the flush policy set with [`Term::set_flush_policy`].
+    #[must_use]
+    pub fn flush_policy() -> FlushPolicy {
+        match FLUSH_POLICY.load(Ordering::Relaxed) {
+            1 => FlushPolicy::Newline,
+            2 => FlushPolicy::Manual,
+            _ => FlushPolicy::PerCall,
+        }
+    }
+
+    /// Returns a handle to stdout that shares the buffer of printed text, so
+    /// that terminal commands written through it keep their order with it.
+    #[must_use]
+    pub fn stdout() -> SharedStdout {
+        SharedStdout { _private: () }
+    }
+
+    /// Writes and flushes any printed text held back by the flush policy.
+    pub fn flush_output() -> io::Result<()> {
+        if STDOUT_PENDING.held.load(Ordering::Acquire) {
+            flush_pending(&STDOUT_PENDING, &mut io::stdout().lock())?;
+        }
+
+        if STDERR_PENDING.held.load(Ordering::Acquire) {
+            flush_pending(&STDERR_PENDING, &mut io::stderr().lock())?;
+        }
 
         Ok(())
     }
 }
 
+// Adds output to the pending output for a stream with `add`, then writes as
+// much of it as the flush policy allows.
+fn emit<W: Write>(
+    pending: &Pending,
+    w: &mut W,
+    add: impl FnOnce(&mut Vec<u8>) -> io::Result<()>
+) -> io::Result<()> {
+    let mut buf = lock(&pending.buf);
+    let start = buf.len();
+
+    if let Err(e) = add(&mut buf) {
+        buf.truncate(start);
+        return Err(e);
+    }
+
+    let len = if buf.len() >= MAX_PENDING {
+        buf.len()
+    } else {
+        ready_len(&buf, buf.len() - start, Term::flush_policy())
+    };
+
+    let result = write_pending(&mut buf, len, w);
+    pending.held.store(!buf.is_empty(), Ordering::Release);
+    result
+}
+
+// Returns how many bytes of `pending`, which ends with `added` newly printed
+// bytes, the flush policy allows to be written.
+fn ready_len(pending: &[u8], added: usize, policy: FlushPolicy) -> usize {
+    // Only the new text needs searching, as any earlier newline was written.
+    let start = pending.len() - added;
+
+    match policy {
+        FlushPolicy::PerCall => pending.len(),
+        FlushPolicy::Newline => pending[start..]
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |i| start + i + 1),
+        FlushPolicy::Manual => 0,
+    }
+}
+
+// Writes and flushes all of the pending output for a stream.
+fn flush_pending<W: Write>(pending: &Pending, w: &mut W) -> io::Result<()> {
+    let mut buf = lock(&pending.buf);
+    let len = buf.len();
+
+    let result = write_pending(&mut buf, len, w);
+    pending.held.store(false, Ordering::Release);
+    result
+}
+
+// Writes and flushes the first `len` bytes of `pending`, removing them.
+fn write_pending<W: Write>(pending: &mut Vec<u8>, len: usize, w: &mut W) -> io::Result<()> {
+    if len == 0 {
+        return Ok(());
+    }
+
+    let result = w.write_all(&pending[..len]).and_then(|()| w.flush());
+    pending.drain(..len);
+    result
+}
+
+// Decides whether to style output under `ColorPolicy::Auto`, following the
+// `NO_COLOR` and `CLICOLOR_FORCE` conventions.
+fn auto_enabled(
+    is_terminal: bool,
+    no_color: Option<&OsStr>,
+    force: Option<&OsStr>,
+    term: Option<&OsStr>
+) -> bool {
+    let is_set = |var: Option<&OsStr>| var.is_some_and(|v| !v.is_empty() && v != "0");
+
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        false
+    } else if is_set(force) {
+        true
+    } else {
+        is_terminal && term.is_none_or(|t| t != "dumb")
+    }
+}
+
 impl std::fmt::Display for Fg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
@@ -113,3 +441,56 @@ impl std::fmt::Display for Bg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use super::{auto_enabled, ready_len, write_pending};
+    use crate::{ColorPolicy, FlushPolicy, Style};
+
+    #[test]
+    fn auto_policy_follows_environment() {
+        let var = |v: &'static str| Some(OsStr::new(v));
+
+        assert!(auto_enabled(true, None, None, var("xterm-256color")));
+        assert!(auto_enabled(true, var(""), None, None));
+        assert!(!auto_enabled(false, None, None, var("xterm")));
+        assert!(!auto_enabled(true, None, None, var("dumb")));
+        assert!(!auto_enabled(true, var("1"), var("1"), var("xterm")));
+        assert!(auto_enabled(false, None, var("1"), var("dumb")));
+        assert!(!auto_enabled(false, None, var("0"), None));
+    }
+
+    #[test]
+    fn fixed_policies_ignore_target() {
+        assert!(ColorPolicy::Always.enabled(false));
+        assert!(!ColorPolicy::Never.enabled(true));
+    }
+
+    #[test]
+    fn flush_policy_limits_written_output() {
+        assert_eq!(ready_len(b"ab\ncd", 2, FlushPolicy::PerCall), 5);
+        assert_eq!(ready_len(b"ab\ncd", 5, FlushPolicy::Newline), 3);
+        assert_eq!(ready_len(b"ab\ncd", 2, FlushPolicy::Newline), 0);
+        assert_eq!(ready_len(b"abcd", 4, FlushPolicy::Newline), 0);
+        assert_eq!(ready_len(b"ab\ncd", 5, FlushPolicy::Manual), 0);
+    }
+
+    #[test]
+    fn writes_pending_output() {
+        let mut pending = b"ab\ncd".to_vec();
+        let mut out = Vec::new();
+
+        write_pending(&mut pending, 3, &mut out).unwrap();
+
+        assert_eq!(out, b"ab\n");
+        assert_eq!(pending, b"cd");
+    }
+
+    #[test]
+    fn renders_without_colors() {
+        assert_eq!(Style::this("X").red().bold().render(false, false), "X");
+        assert_eq!(Style::this("X").red().render(true, false), "\x1b[31mX\x1b[0m");
+    }
+}
@@ -0,0 +1,78 @@
+//! Checks that styled output is built and written without heap allocations.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt::Write as _,
+    io::Write as _,
+};
+
+use term_mods::{ColorPolicy, Command, Style, Term};
+
+// Counts the allocations made by each thread, so that other tests running at
+// the same time are not counted.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// Returns the number of allocations made by `f` on this thread.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+// Styles are built at compile time.
+const WARNING: Style<'static> = Style::this("warning").bold().fg_rgb(255, 128, 0).bg_rgb(0, 0, 64);
+
+const SGR: &str = "\x1b[1;38;2;255;128;0;48;2;0;0;64m";
+
+#[test]
+fn styled_output_does_not_allocate() {
+    Term::set_color_policy(ColorPolicy::Always);
+
+    let mut style = WARNING;
+    let mut bytes = Vec::with_capacity(1024);
+    let mut text = String::with_capacity(1024);
+
+    // Reads the color policy environment, which happens once.
+    style.write_to(&mut bytes).unwrap();
+    bytes.clear();
+
+    // Building a `String` does allocate, which shows the count is working.
+    assert!(allocations(|| drop(style.get_ansi())) > 0);
+
+    assert_eq!(allocations(|| assert_eq!(style.sgr().as_str(), SGR)), 0);
+    assert_eq!(allocations(|| style.write_to(&mut bytes).unwrap()), 0);
+    assert_eq!(allocations(|| write!(bytes, "{}", Command::PrintStyled(style)).unwrap()), 0);
+    assert_eq!(allocations(|| style.write_to_fmt(&mut text).unwrap()), 0);
+    assert_eq!(allocations(|| write!(text, "{}", Command::MoveTo(3, 5)).unwrap()), 0);
+
+    assert_eq!(bytes, format!("{SGR}warning\x1b[0m{SGR}warning\x1b[0m").as_bytes());
+    assert_eq!(text, format!("{SGR}warning\x1b[0m\x1b[3;5H"));
+}
@@ -0,0 +1,74 @@
+//! Checks that terminal commands written through `Term::stdout` are held
+//! along with printed text, keeping their order.
+
+use std::{
+    env,
+    io::{self, Write},
+    process::Command,
+};
+
+use term_mods::{FlushPolicy, Style, Term};
+
+// Set to the name of the test when this binary is run again to produce the
+// output under test.
+const CHILD_VAR: &str = "TERM_MODS_FLUSH_CHILD";
+
+// Runs the named test alone in a child process and returns its stdout, so
+// that the output can be checked and the flush policy does not affect other
+// tests.
+fn run_child(name: &str) -> String {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", name, "--nocapture"])
+        .env(CHILD_VAR, name)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn is_child(name: &str) -> bool {
+    env::var_os(CHILD_VAR).is_some_and(|v| v == name)
+}
+
+// Moves the cursor and prints a cell on each of three rows.
+fn draw_cells() {
+    let mut stdout = Term::stdout();
+
+    for row in 1..=3 {
+        Term::cursor_goto(row, 1, &mut stdout).unwrap();
+        Style::this("X").print().unwrap();
+    }
+}
+
+#[test]
+fn held_text_is_not_overtaken_by_commands() {
+    if is_child("held_text_is_not_overtaken_by_commands") {
+        Term::set_flush_policy(FlushPolicy::Newline);
+        draw_cells();
+        Term::flush_output().unwrap();
+        return;
+    }
+
+    let stdout = run_child("held_text_is_not_overtaken_by_commands");
+    assert!(stdout.contains("\x1b[1;1HX\x1b[2;1HX\x1b[3;1HX"), "{stdout:?}");
+}
+
+#[test]
+fn manual_policy_holds_commands_until_flushed() {
+    if is_child("manual_policy_holds_commands_until_flushed") {
+        Term::set_flush_policy(FlushPolicy::Manual);
+        draw_cells();
+
+        // Written directly, so it arrives before anything still held.
+        io::stdout().write_all(b"|").unwrap();
+        io::stdout().flush().unwrap();
+
+        Term::flush_output().unwrap();
+        return;
+    }
+
+    let stdout = run_child("manual_policy_holds_commands_until_flushed");
+    assert!(stdout.contains("|\x1b[1;1HX\x1b[2;1HX\x1b[3;1HX"), "{stdout:?}");
+}
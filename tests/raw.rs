@@ -0,0 +1,82 @@
+//! Checks that nested raw mode guards each restore the settings they
+//! replaced. The test runs in a child process attached to a pseudoterminal,
+//! so that it does not depend on, or change, the terminal running the tests.
+
+#![cfg(unix)]
+
+use std::{
+    env,
+    fs::File,
+    os::{fd::FromRawFd, unix::process::CommandExt},
+    process::{Command, Stdio},
+};
+
+use term_mods::Term;
+
+// Set when this binary is run again inside the pseudoterminal.
+const CHILD_VAR: &str = "TERM_MODS_RAW_CHILD";
+
+// Returns the local modes of the terminal on stdin.
+fn lflag() -> libc::tcflag_t {
+    // SAFETY: All-zero bytes are a valid `termios`, which `tcgetattr` fills.
+    unsafe {
+        let mut t: libc::termios = std::mem::zeroed();
+        assert_eq!(libc::tcgetattr(libc::STDIN_FILENO, &raw mut t), 0);
+        t.c_lflag
+    }
+}
+
+#[test]
+fn nested_guards_restore_previous_mode() {
+    if env::var_os(CHILD_VAR).is_some() {
+        assert_ne!(lflag() & libc::ICANON, 0);
+
+        let cbreak = Term::enable_cbreak_mode().unwrap();
+        assert_eq!(lflag() & (libc::ICANON | libc::ISIG), libc::ISIG);
+
+        let raw = Term::enable_raw_mode().unwrap();
+        assert_eq!(lflag() & (libc::ICANON | libc::ISIG), 0);
+
+        drop(raw);
+        assert_eq!(lflag() & (libc::ICANON | libc::ISIG), libc::ISIG);
+
+        drop(cbreak);
+        assert_eq!(lflag() & (libc::ICANON | libc::ISIG), libc::ICANON | libc::ISIG);
+        return;
+    }
+
+    let (mut master, mut slave) = (0, 0);
+
+    // SAFETY: The out pointers are valid, and the optional arguments are null.
+    let opened = unsafe {
+        libc::openpty(
+            &raw mut master,
+            &raw mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null()
+        )
+    };
+    assert_eq!(opened, 0);
+
+    // SAFETY: Both descriptors were just opened and are owned here.
+    let (_master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+
+    let mut child = Command::new(env::current_exe().unwrap());
+    child
+        .args(["--exact", "nested_guards_restore_previous_mode", "--nocapture"])
+        .env(CHILD_VAR, "1")
+        .stdin(Stdio::from(slave));
+
+    // Starting a new session detaches the child from the terminal running
+    // the tests, so the crate falls back to the pseudoterminal on stdin.
+    // SAFETY: `setsid` is async-signal-safe.
+    unsafe {
+        child.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+
+    assert!(child.status().unwrap().success());
+}
@@ -0,0 +1,41 @@
+//! Checks that the resize handler chains to the `SIGWINCH` handler it
+//! replaces. This runs in its own process, as it changes signal dispositions.
+
+#![cfg(unix)]
+
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use term_mods::Term;
+
+// Set by the previously installed handler when it receives its arguments.
+static CHAINED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn previous(_sig: libc::c_int, info: *mut libc::siginfo_t, _context: *mut c_void) {
+    if !info.is_null() {
+        CHAINED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn chains_previous_siginfo_handler() {
+    type Handler = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut c_void);
+
+    // SAFETY: `action` is fully initialized before it is installed.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = previous as Handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO;
+        libc::sigemptyset(&raw mut action.sa_mask);
+        assert_eq!(libc::sigaction(libc::SIGWINCH, &raw const action, std::ptr::null_mut()), 0);
+    }
+
+    let _events = Term::on_resize().unwrap();
+
+    // SAFETY: Both handlers only touch atomics and a pipe.
+    unsafe { libc::raise(libc::SIGWINCH) };
+
+    assert!(CHAINED.load(Ordering::SeqCst));
+}
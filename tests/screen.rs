@@ -0,0 +1,84 @@
+//! Checks that the alternate screen guard restores the main screen when it is
+//! dropped or the program panics. Each test runs in a child process, as the
+//! guard switches the screen of the process-wide stdout.
+
+use std::{env, process::Command};
+
+use term_mods::Term;
+
+// Set to the name of the test when this binary is run again to produce the
+// output under test.
+const CHILD_VAR: &str = "TERM_MODS_SCREEN_CHILD";
+
+const ENTER: &str = "\x1b[?1049h";
+const LEAVE: &str = "\x1b[?1049l";
+
+// Runs the named test alone in a child process and returns whether it
+// succeeded along with its stdout.
+fn run_child(name: &str) -> (bool, String) {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", name, "--nocapture"])
+        .env(CHILD_VAR, name)
+        .output()
+        .unwrap();
+
+    (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn is_child(name: &str) -> bool {
+    env::var_os(CHILD_VAR).is_some_and(|v| v == name)
+}
+
+// Returns where `pat` first appears in `stdout`, failing if it is missing.
+fn find(stdout: &str, pat: &str) -> usize {
+    stdout.find(pat).unwrap_or_else(|| panic!("{pat:?} missing from {stdout:?}"))
+}
+
+#[test]
+fn guard_leaves_alt_screen_on_drop() {
+    if is_child("guard_leaves_alt_screen_on_drop") {
+        let screen = Term::alt_screen().unwrap();
+        print!("drawn");
+        drop(screen);
+        print!("after");
+        return;
+    }
+
+    let (success, stdout) = run_child("guard_leaves_alt_screen_on_drop");
+    assert!(success);
+
+    assert!(find(&stdout, ENTER) < find(&stdout, "drawn"), "{stdout:?}");
+    assert!(find(&stdout, "drawn") < find(&stdout, LEAVE), "{stdout:?}");
+    assert!(find(&stdout, LEAVE) < find(&stdout, "after"), "{stdout:?}");
+}
+
+#[test]
+fn nested_guard_does_not_switch_screens() {
+    if is_child("nested_guard_does_not_switch_screens") {
+        let outer = Term::alt_screen().unwrap();
+        drop(Term::alt_screen().unwrap());
+        print!("inner dropped");
+        drop(outer);
+        return;
+    }
+
+    let (success, stdout) = run_child("nested_guard_does_not_switch_screens");
+    assert!(success);
+
+    assert_eq!(stdout.matches(ENTER).count(), 1, "{stdout:?}");
+    assert_eq!(stdout.matches(LEAVE).count(), 1, "{stdout:?}");
+    assert!(find(&stdout, "inner dropped") < find(&stdout, LEAVE), "{stdout:?}");
+}
+
+#[test]
+fn panic_leaves_alt_screen() {
+    if is_child("panic_leaves_alt_screen") {
+        // Never dropped, so only the panic hook can leave the screen.
+        std::mem::forget(Term::alt_screen().unwrap());
+        panic!("while on the alternate screen");
+    }
+
+    let (success, stdout) = run_child("panic_leaves_alt_screen");
+    assert!(!success);
+    assert!(find(&stdout, ENTER) < find(&stdout, LEAVE), "{stdout:?}");
+}